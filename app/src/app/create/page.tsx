@@ -88,7 +88,8 @@ export default function CreateMarketPage() {
       const endTime = Math.floor(Date.now() / 1000) + formData.duration * 3600;
       
       const [platformPda] = getPlatformPDA();
-      const [marketPda] = getMarketPDA(publicKey, formData.question);
+      const platform = await program.account.platform.fetch(platformPda);
+      const [marketPda] = getMarketPDA(platform.totalMarkets);
 
       const categoryObj = { [formData.category]: {} };
       
@@ -28,10 +28,10 @@ export function getPlatformPDA() {
   );
 }
 
-export function getMarketPDA(authority: web3.PublicKey, question: string) {
-  const questionSeed = question.slice(0, Math.min(question.length, 32));
+// Markets are keyed by creation order; the next one uses platform.totalMarkets
+export function getMarketPDA(index: BN | number) {
   return web3.PublicKey.findProgramAddressSync(
-    [Buffer.from("market"), authority.toBuffer(), Buffer.from(questionSeed)],
+    [Buffer.from("market"), new BN(index).toArrayLike(Buffer, "le", 8)],
     PROGRAM_ID
   );
 }
@@ -1,12 +1,45 @@
+// Anchor's generated IDL instructions still call the deprecated `AccountInfo::realloc`.
+// `#[program]` emits them at the crate root beside the program module, so no
+// narrower item can carry this allow.
+#![allow(deprecated)]
+
+
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
-use anchor_spl::token::{self, Token, Mint, TokenAccount, MintTo};
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::token::{self, Token, Mint, TokenAccount, MintTo, Burn, CloseAccount, Transfer};
 use anchor_spl::associated_token::AssociatedToken;
-use pyth_sdk_solana::state::load_price_account;
+use anchor_spl::metadata::{
+    create_master_edition_v3, create_metadata_accounts_v3, mpl_token_metadata::types::DataV2,
+    CreateMasterEditionV3, CreateMetadataAccountsV3, Metadata,
+};
+use pyth_sdk_solana::state::{load_price_account, SolanaPriceAccount};
+
+pub mod lmsr;
 
 declare_id!("ocKzKFLEt9dWXtPmD1xQSvGgA7ugaFFkGv4oXnWNa2N");
 
 const PLATFORM_FEE_BPS: u64 = 200; // 2% platform fee
+const DEFAULT_EARLY_EXIT_PENALTY_BPS: u16 = 500; // 5% forfeited on early withdrawal
+const POSITION_MINT_DECIMALS: u8 = 9; // Position tokens are minted 1:1 with staked lamports
+const CARD_MULTIPLIER_BASE: u64 = 1000; // Card multiplier of 1000 = 1x payout
+const MAX_RARITY_TIERS: usize = 5; // Common, Uncommon, Rare, Epic, Legendary
+const RARITY_NAMES: [&str; MAX_RARITY_TIERS] = ["Common", "Uncommon", "Rare", "Epic", "Legendary"];
+const MAX_URI_TEMPLATE_LEN: usize = 150; // Leaves room for the rarity name within Metaplex's 200-byte URI
+const CARD_SYMBOL: &str = "PCARD";
+const XP_STAKE_UNIT: u64 = 10_000_000; // 0.01 SOL of net battle stake earns 1 XP at even odds
+const FUSION_CARD_COUNT: usize = 3; // Same-rarity cards burned to roll one card of the next tier
+const RESOLUTION_GRACE_PERIOD: i64 = 86_400; // Default: markets must be resolved within a day of end_time
+const DISPUTE_WINDOW: i64 = 172_800; // Two days after resolution to dispute it before creator stakes unlock
+const CREATOR_REPUTATION_RAMP: u64 = 10; // Net on-time resolutions that halve the creator stake
+const LEADERBOARD_SIZE: usize = 10; // Ranked wallets kept on-chain per season
+const LEADERBOARD_PRIZE_BPS: [u64; LEADERBOARD_SIZE] = [3000, 2000, 1500, 1000, 800, 600, 400, 300, 200, 200];
+const LEADERBOARD_SETTLEMENT_PERIOD: i64 = 259_200; // Default: three days after a season ends to settle bets before prizes
+const BOOK_SHARE_LAMPORTS: u64 = 10_000; // Order book share payout; prices are quoted in lamports per share
+const MAX_BOOK_ORDERS: usize = 32; // Resting orders per side
+const MIN_BOOK_ORDER_QUANTITY: u64 = 100; // Smallest order, so dust cannot fill the book
+const MAX_ORDERS_PER_OWNER: usize = 4; // Resting orders one wallet may hold per side
+const MAX_BOOK_EVENTS: usize = 32; // Unconsumed fills per market
 
 #[program]
 pub mod prediction {
@@ -18,11 +51,159 @@ pub mod prediction {
         platform.treasury = ctx.accounts.treasury.key();
         platform.total_markets = 0;
         platform.total_volume = 0;
+        platform.early_exit_penalty_bps = DEFAULT_EARLY_EXIT_PENALTY_BPS;
+        platform.total_bonus_funded = 0;
+        platform.total_bonus_paid = 0;
+        platform.current_season = 0;
+        platform.leaderboard_fee_bps = 0;
+        platform.referral_fee_bps = 0;
+        platform.creator_stake_base = 0;
+        platform.manual_market_bond = 0;
+        platform.resolution_grace_period = RESOLUTION_GRACE_PERIOD;
+        platform.leaderboard_settlement_period = LEADERBOARD_SETTLEMENT_PERIOD;
         platform.bump = ctx.bumps.platform;
-        
+    
+        Ok(())
+    }
+
+    pub fn set_early_exit_penalty(
+        ctx: Context<UpdatePlatform>,
+        penalty_bps: u16,
+    ) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        require!(
+            ctx.accounts.authority.key() == platform.authority,
+            PredictionError::Unauthorized
+        );
+        require!(penalty_bps <= 10000, PredictionError::InvalidBasisPoints);
+
+        platform.early_exit_penalty_bps = penalty_bps;
+
+        Ok(())
+    }
+
+    // Share of each collected platform fee routed to the season's leaderboard
+    pub fn set_leaderboard_fee_share(
+        ctx: Context<UpdatePlatform>,
+        share_bps: u16,
+    ) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        require!(
+            ctx.accounts.authority.key() == platform.authority,
+            PredictionError::Unauthorized
+        );
+        require!(share_bps <= 10000, PredictionError::InvalidBasisPoints);
+
+        platform.leaderboard_fee_bps = share_bps;
+
+        Ok(())
+    }
+
+    // Share of the platform fee on a referred user's winnings credited to the referrer
+    pub fn set_referral_fee_share(
+        ctx: Context<UpdatePlatform>,
+        share_bps: u16,
+    ) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        require!(
+            ctx.accounts.authority.key() == platform.authority,
+            PredictionError::Unauthorized
+        );
+        require!(share_bps <= 10000, PredictionError::InvalidBasisPoints);
+
+        platform.referral_fee_bps = share_bps;
+
+        Ok(())
+    }
+
+    // Stake a creator with no track record locks per market; see CreatorProfile::required_stake
+    pub fn set_creator_stake_base(
+        ctx: Context<UpdatePlatform>,
+        stake_base: u64,
+    ) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        require!(
+            ctx.accounts.authority.key() == platform.authority,
+            PredictionError::Unauthorized
+        );
+
+        platform.creator_stake_base = stake_base;
+
+        Ok(())
+    }
+
+    // Bond a creator locks on every manually resolved market
+    pub fn set_manual_market_bond(
+        ctx: Context<UpdatePlatform>,
+        bond: u64,
+    ) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        require!(
+            ctx.accounts.authority.key() == platform.authority,
+            PredictionError::Unauthorized
+        );
+
+        platform.manual_market_bond = bond;
+
+        Ok(())
+    }
+
+    // Time after end_time a new market has to be resolved before anyone can void it
+    pub fn set_resolution_grace_period(
+        ctx: Context<UpdatePlatform>,
+        grace_period: i64,
+    ) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        require!(
+            ctx.accounts.authority.key() == platform.authority,
+            PredictionError::Unauthorized
+        );
+        require!(grace_period > 0, PredictionError::InvalidGracePeriod);
+
+        platform.resolution_grace_period = grace_period;
+
+        Ok(())
+    }
+
+    // Time after a season ends for its bets to settle before prizes can be paid
+    pub fn set_leaderboard_settlement_period(
+        ctx: Context<UpdatePlatform>,
+        settlement_period: i64,
+    ) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        require!(
+            ctx.accounts.authority.key() == platform.authority,
+            PredictionError::Unauthorized
+        );
+        require!(settlement_period >= 0, PredictionError::InvalidSettlementPeriod);
+
+        platform.leaderboard_settlement_period = settlement_period;
+
+        Ok(())
+    }
+
+    pub fn fund_bonus_pool(
+        ctx: Context<FundBonusPool>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, PredictionError::InvalidAmount);
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.funder.to_account_info(),
+                to: ctx.accounts.bonus_pool.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_context, amount)?;
+
+        let platform = &mut ctx.accounts.platform;
+        platform.total_bonus_funded = platform.total_bonus_funded.checked_add(amount).unwrap();
+
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_market(
         ctx: Context<CreateMarket>,
         question: String,
@@ -78,46 +259,102 @@ pub mod prediction {
         market.question = question;
         market.description = description;
         market.end_time = end_time;
+        market.resolution_deadline = end_time
+            .checked_add(ctx.accounts.platform.resolution_grace_period)
+            .unwrap();
         market.resolved = false;
         market.outcome = None;
         market.total_yes_amount = 0;
         market.total_no_amount = 0;
+        market.forfeited_amount = 0;
+        market.fee_collected = false;
+        market.referral_paid = 0;
+        market.referred_yes_amount = 0;
+        market.referred_no_amount = 0;
+        market.resolved_at = 0;
+        market.dispute = DisputeStatus::None;
+        market.stake_released = false;
+        market.yes_mint = ctx.accounts.yes_mint.key();
+        market.no_mint = ctx.accounts.no_mint.key();
         market.category = category;
         market.creator = ctx.accounts.authority.key();
         market.created_at = Clock::get()?.unix_timestamp;
         market.bump = ctx.bumps.market;
-        
+    
         // Oracle configuration
         market.oracle_source = oracle_source;
         market.oracle_data_type = oracle_data_type;
-        
+    
         // Price oracle fields
         market.price_feed = price_feed;
         market.target_price = target_price;
         market.strike_price = None;
-        
+    
         // Sports oracle fields
         market.game_id = game_id;
         market.team_a_score = None;
         market.team_b_score = None;
         market.target_spread = target_spread;
-        
+    
         // Weather oracle fields
         market.location = location;
         market.weather_metric = weather_metric.unwrap_or_default();
         market.target_value = target_value;
         market.recorded_value = None;
-        
+    
         // Social/Custom oracle fields
         market.data_identifier = data_identifier;
         market.metric_type = metric_type.unwrap_or_default();
         market.threshold = threshold;
         market.actual_value = None;
 
-        // Update platform stats
+        // Update platform stats; the next market takes the next index
         let platform = &mut ctx.accounts.platform;
+        market.index = platform.total_markets;
+        market.season_id = platform.current_season;
         platform.total_markets = platform.total_markets.checked_add(1).unwrap();
 
+        // Creators with a better resolution record lock a smaller stake
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        creator_profile.init_if_new(ctx.accounts.authority.key(), ctx.bumps.creator_profile);
+        let creator_stake = creator_profile.required_stake(platform.creator_stake_base);
+        // Manual markets also lock a bond that is slashed to bettors if the
+        // creator misses the resolution deadline
+        let creator_bond = if market.oracle_source == OracleSource::Manual {
+            platform.manual_market_bond
+        } else {
+            0
+        };
+        let locked = creator_stake.checked_add(creator_bond).unwrap();
+        if locked > 0 {
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: creator_profile.to_account_info(),
+                },
+            );
+            system_program::transfer(cpi_context, locked)?;
+        }
+        creator_profile.markets_created = creator_profile.markets_created.checked_add(1).unwrap();
+        creator_profile.locked_stake = creator_profile.locked_stake.checked_add(locked).unwrap();
+        market.creator_stake = creator_stake;
+        market.creator_bond = creator_bond;
+
+        emit!(MarketCreated {
+            market: market.key(),
+            index: market.index,
+            creator: market.creator,
+            question: market.question.clone(),
+            category: market.category,
+            oracle_source: market.oracle_source,
+            oracle_data_type: market.oracle_data_type,
+            end_time: market.end_time,
+            yes_mint: market.yes_mint,
+            no_mint: market.no_mint,
+            timestamp: market.created_at,
+        });
+
         Ok(())
     }
 
@@ -127,7 +364,7 @@ pub mod prediction {
         prediction: bool,
     ) -> Result<()> {
         require!(amount > 0, PredictionError::InvalidAmount);
-        
+    
         let market = &mut ctx.accounts.market;
         require!(!market.resolved, PredictionError::MarketAlreadyResolved);
         require!(Clock::get()?.unix_timestamp < market.end_time, PredictionError::MarketEnded);
@@ -142,6 +379,25 @@ pub mod prediction {
         );
         system_program::transfer(cpi_context, amount)?;
 
+        // Mint transferable position tokens for the chosen side
+        let market_key = market.key();
+        let bump = ctx.bumps.vault;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            market_key.as_ref(),
+            &[bump],
+        ]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.position_mint.to_account_info(),
+                to: ctx.accounts.position_token_account.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::mint_to(cpi_ctx, amount)?;
+
         // Update market totals (running totals for performance)
         if prediction {
             market.total_yes_amount = market.total_yes_amount.checked_add(amount).unwrap();
@@ -151,16 +407,129 @@ pub mod prediction {
 
         // Initialize or update bet account
         let bet = &mut ctx.accounts.bet;
+        let first_bet = bet.market == Pubkey::default();
         bet.market = market.key();
         bet.bettor = ctx.accounts.bettor.key();
-        bet.amount = bet.amount.checked_add(amount).unwrap();
-        bet.prediction = prediction;
-        bet.claimed = false;
+        if prediction {
+            bet.yes_amount = bet.yes_amount.checked_add(amount).unwrap();
+        } else {
+            bet.no_amount = bet.no_amount.checked_add(amount).unwrap();
+        }
 
         // Update platform volume
         let platform = &mut ctx.accounts.platform;
         platform.total_volume = platform.total_volume.checked_add(amount).unwrap();
 
+        let profile = &mut ctx.accounts.user_profile;
+        profile.init_if_new(ctx.accounts.bettor.key(), ctx.bumps.user_profile);
+        profile.attach_referrer(ctx.accounts.referrer_profile.as_deref())?;
+        profile.record_wager(amount, first_bet);
+        if profile.referrer.is_some() {
+            market.record_referred_stake(amount, prediction);
+        }
+
+        emit!(BetPlaced {
+            market: market.key(),
+            bettor: bet.bettor,
+            prediction,
+            amount,
+            card: None,
+            total_yes_amount: market.total_yes_amount,
+            total_no_amount: market.total_no_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_bet(
+        ctx: Context<WithdrawBet>,
+        amount: u64,
+        prediction: bool,
+    ) -> Result<()> {
+        require!(amount > 0, PredictionError::InvalidAmount);
+
+        let market = &mut ctx.accounts.market;
+        require!(!market.resolved, PredictionError::MarketAlreadyResolved);
+        require!(Clock::get()?.unix_timestamp < market.end_time, PredictionError::MarketEnded);
+
+        let bet = &mut ctx.accounts.bet;
+        require!(bet.bettor == ctx.accounts.bettor.key(), PredictionError::Unauthorized);
+        let staked = if prediction { bet.yes_amount } else { bet.no_amount };
+        require!(staked >= amount, PredictionError::InsufficientStake);
+
+        // The penalty is forfeited to the pool and paid out to the eventual winners
+        let penalty = amount
+            .checked_mul(ctx.accounts.platform.early_exit_penalty_bps as u64).unwrap()
+            .checked_div(10000).unwrap();
+        let refund = amount.checked_sub(penalty).unwrap();
+
+        // Withdrawals come out of the battle stake last
+        if prediction {
+            bet.yes_amount = bet.yes_amount.checked_sub(amount).unwrap();
+            bet.card_yes_amount = bet.card_yes_amount.min(bet.yes_amount);
+            market.total_yes_amount = market.total_yes_amount.checked_sub(amount).unwrap();
+        } else {
+            bet.no_amount = bet.no_amount.checked_sub(amount).unwrap();
+            bet.card_no_amount = bet.card_no_amount.min(bet.no_amount);
+            market.total_no_amount = market.total_no_amount.checked_sub(amount).unwrap();
+        }
+        market.forfeited_amount = market.forfeited_amount.checked_add(penalty).unwrap();
+        if ctx.accounts.user_profile.referrer.is_some() {
+            if prediction {
+                market.referred_yes_amount = market.referred_yes_amount.saturating_sub(amount);
+            } else {
+                market.referred_no_amount = market.referred_no_amount.saturating_sub(amount);
+            }
+        }
+
+        // Burn the matching position tokens so the stake cannot also be redeemed
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.position_mint.to_account_info(),
+                from: ctx.accounts.position_token_account.to_account_info(),
+                authority: ctx.accounts.bettor.to_account_info(),
+            },
+        );
+        token::burn(cpi_ctx, amount)?;
+
+        // Transfer refund from vault to bettor
+        let market_key = market.key();
+        let bump = ctx.bumps.vault;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            market_key.as_ref(),
+            &[bump],
+        ]];
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.bettor.key(),
+            refund,
+        );
+
+        anchor_lang::solana_program::program::invoke_signed(
+            &transfer_ix,
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.bettor.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        emit!(BetWithdrawn {
+            market: market_key,
+            bettor: ctx.accounts.bettor.key(),
+            prediction,
+            amount,
+            penalty,
+            total_yes_amount: market.total_yes_amount,
+            total_no_amount: market.total_no_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 
@@ -178,13 +547,106 @@ pub mod prediction {
             ctx.accounts.authority.key() == market.authority,
             PredictionError::Unauthorized
         );
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= market.end_time, PredictionError::MarketNotEnded);
+
+        // A late resolution still settles the market, but the bond goes to bettors
+        if now > market.resolution_deadline {
+            slash_creator_bond(market, &mut ctx.accounts.creator_profile, &ctx.accounts.vault)?;
+        }
+
+        market.resolved = true;
+        market.outcome = Some(outcome);
+        market.resolved_at = now;
+        emit_market_resolved(market)?;
+
+        Ok(())
+    }
+
+    // Permissionless: voids any market nobody resolved by its resolution
+    // deadline, whatever its oracle source. The creator stake, and a manual
+    // market's bond, are forfeited into the pool and bettors reclaim their
+    // stakes plus a share of them through claim_refund. LMSR and order-book
+    // traders get their cost basis back through their own claims.
+    pub fn expire_market(ctx: Context<ExpireMarket>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(!market.resolved, PredictionError::MarketAlreadyResolved);
+        let now = Clock::get()?.unix_timestamp;
         require!(
-            Clock::get()?.unix_timestamp >= market.end_time,
-            PredictionError::MarketNotEnded
+            now > market.resolution_deadline,
+            PredictionError::ResolutionDeadlineNotReached
         );
 
+        // Only the creator of a manual market is on the hook for resolving it
+        if market.oracle_source == OracleSource::Manual {
+            slash_creator_bond(market, &mut ctx.accounts.creator_profile, &ctx.accounts.vault)?;
+            let creator_profile = &mut ctx.accounts.creator_profile;
+            creator_profile.cancelled = creator_profile.cancelled.checked_add(1).unwrap();
+        }
+        // The stake backed getting the market resolved, so it is not returned
+        forfeit_creator_stake(market, &mut ctx.accounts.creator_profile, &ctx.accounts.vault)?;
+
         market.resolved = true;
-        market.outcome = Some(outcome);
+        market.outcome = None;
+        market.resolved_at = now;
+        emit_market_resolved(market)?;
+
+        Ok(())
+    }
+
+    // Refunds position tokens of either side on a voided market, pro rata
+    // over the whole pool (stakes, forfeits and any slashed bond); no fee
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(market.resolved, PredictionError::MarketNotResolved);
+        require!(market.outcome.is_none(), PredictionError::MarketNotVoided);
+        require!(
+            ctx.accounts.position_mint.key() == market.yes_mint
+                || ctx.accounts.position_mint.key() == market.no_mint,
+            PredictionError::InvalidPosition
+        );
+
+        let amount = ctx.accounts.position_token_account.amount;
+        require!(amount > 0, PredictionError::EmptyPosition);
+
+        let staked = market.total_yes_amount.checked_add(market.total_no_amount).unwrap();
+        let refund = (amount as u128)
+            .checked_mul(market.total_pool() as u128).unwrap()
+            .checked_div(staked as u128).unwrap() as u64;
+
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.position_mint.to_account_info(),
+                from: ctx.accounts.position_token_account.to_account_info(),
+                authority: ctx.accounts.bettor.to_account_info(),
+            },
+        );
+        token::burn(cpi_ctx, amount)?;
+
+        let market_key = market.key();
+        let bump = ctx.bumps.vault;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            market_key.as_ref(),
+            &[bump],
+        ]];
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.bettor.key(),
+            refund,
+        );
+
+        anchor_lang::solana_program::program::invoke_signed(
+            &transfer_ix,
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.bettor.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
 
         Ok(())
     }
@@ -206,9 +668,9 @@ pub mod prediction {
         // Load and validate Pyth price feed
         let price_feed_data = &ctx.accounts.price_feed.try_borrow_data()
             .map_err(|_| PredictionError::InvalidPriceFeed)?;
-        let price_account: &pyth_sdk_solana::state::PriceAccount = load_price_account(price_feed_data.as_ref())
+        let price_account: &SolanaPriceAccount = load_price_account(price_feed_data.as_ref())
             .map_err(|_| PredictionError::InvalidPriceFeed)?;
-        
+    
         // Get current price
         let current_price = price_account.agg.price;
         let publish_time = price_account.timestamp;
@@ -222,13 +684,15 @@ pub mod prediction {
 
         // Store the actual price at resolution time
         market.strike_price = Some(current_price);
-        
+    
         // Determine outcome: YES if current price >= target price
         let target = market.target_price.unwrap();
         let outcome = current_price >= target;
 
         market.resolved = true;
         market.outcome = Some(outcome);
+        market.resolved_at = Clock::get()?.unix_timestamp;
+        emit_market_resolved(market)?;
 
         Ok(())
     }
@@ -274,6 +738,8 @@ pub mod prediction {
 
         market.resolved = true;
         market.outcome = Some(outcome);
+        market.resolved_at = Clock::get()?.unix_timestamp;
+        emit_market_resolved(market)?;
 
         Ok(())
     }
@@ -304,6 +770,8 @@ pub mod prediction {
 
         market.resolved = true;
         market.outcome = Some(outcome);
+        market.resolved_at = Clock::get()?.unix_timestamp;
+        emit_market_resolved(market)?;
 
         Ok(())
     }
@@ -336,23 +804,30 @@ pub mod prediction {
 
         market.resolved = true;
         market.outcome = Some(outcome);
+        market.resolved_at = Clock::get()?.unix_timestamp;
+        emit_market_resolved(market)?;
 
         Ok(())
     }
 
     pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
         let market = &ctx.accounts.market;
-        let bet = &mut ctx.accounts.bet;
 
         require!(market.resolved, PredictionError::MarketNotResolved);
-        require!(!bet.claimed, PredictionError::AlreadyClaimed);
-        require!(bet.bettor == ctx.accounts.bettor.key(), PredictionError::Unauthorized);
 
-        let outcome = market.outcome.unwrap();
-        require!(bet.prediction == outcome, PredictionError::LosingBet);
+        let outcome = market.outcome.ok_or(PredictionError::MarketVoided)?;
+        let winning_mint = if outcome { market.yes_mint } else { market.no_mint };
+        require!(
+            ctx.accounts.position_mint.key() == winning_mint,
+            PredictionError::LosingBet
+        );
+
+        // Whoever holds the winning position tokens can redeem them
+        let amount = ctx.accounts.position_token_account.amount;
+        require!(amount > 0, PredictionError::EmptyPosition);
 
         // Calculate winnings with platform fee
-        let total_pool = market.total_yes_amount.checked_add(market.total_no_amount).unwrap();
+        let total_pool = market.total_pool();
         let winning_pool = if outcome {
             market.total_yes_amount
         } else {
@@ -365,14 +840,44 @@ pub mod prediction {
         let platform_fee = total_pool
             .checked_mul(PLATFORM_FEE_BPS).unwrap()
             .checked_div(10000).unwrap();
-        
+    
         let pool_after_fee = total_pool.checked_sub(platform_fee).unwrap();
 
         // Calculate user's proportional winnings
-        let winnings = (bet.amount as u128)
+        let winnings = (amount as u128)
             .checked_mul(pool_after_fee as u128).unwrap()
             .checked_div(winning_pool as u128).unwrap() as u64;
 
+        let season_id = market.season_id;
+        let profile = &mut ctx.accounts.user_profile;
+        profile.init_if_new(ctx.accounts.bettor.key(), ctx.bumps.user_profile);
+
+        // Card bonus on the bettor's own battle stake, funded by the bonus pool
+        // rather than by other bettors
+        let mut bonus: u64 = 0;
+        if let Some(bet) = ctx.accounts.bet.as_mut() {
+            settle_bet_result(bet, ctx.accounts.card.as_mut(), profile, market)?;
+
+            // Only stake placed through battle carries the card multiplier
+            let staked = if outcome { bet.card_yes_amount } else { bet.card_no_amount };
+            if bet.card_mint.is_some() && !bet.bonus_claimed && staked > 0 {
+                let boosted_base = (amount.min(staked) as u128)
+                    .checked_mul(pool_after_fee as u128).unwrap()
+                    .checked_div(winning_pool as u128).unwrap();
+                let boosted_payout = boosted_base
+                    .checked_mul(bet.card_multiplier as u128).unwrap()
+                    .checked_div(CARD_MULTIPLIER_BASE as u128).unwrap();
+
+                // Keep the pool rent-exempt; whatever is left caps the bonus
+                let available = ctx.accounts.bonus_pool.lamports()
+                    .saturating_sub(Rent::get()?.minimum_balance(0));
+                bonus = (boosted_payout.saturating_sub(boosted_base) as u64).min(available);
+
+                bet.bonus_claimed = true;
+                bet.bonus_paid = bonus;
+            }
+        }
+
         // Transfer winnings from vault to bettor
         let market_key = market.key();
         let bump = ctx.bumps.vault;
@@ -398,26 +903,156 @@ pub mod prediction {
             signer_seeds,
         )?;
 
-        bet.claimed = true;
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.position_mint.to_account_info(),
+                from: ctx.accounts.position_token_account.to_account_info(),
+                authority: ctx.accounts.bettor.to_account_info(),
+            },
+        );
+        token::burn(cpi_ctx, amount)?;
 
-        Ok(())
-    }
+        if bonus > 0 {
+            let bump = ctx.bumps.bonus_pool;
+            let signer_seeds: &[&[&[u8]]] = &[&[b"bonus_pool", &[bump]]];
 
-    pub fn collect_platform_fee(ctx: Context<CollectPlatformFee>) -> Result<()> {
-        let market = &ctx.accounts.market;
-        require!(market.resolved, PredictionError::MarketNotResolved);
+            let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.bonus_pool.key(),
+                &ctx.accounts.bettor.key(),
+                bonus,
+            );
 
-        let platform = &ctx.accounts.platform;
-        require!(
-            ctx.accounts.authority.key() == platform.authority,
-            PredictionError::Unauthorized
+            anchor_lang::solana_program::program::invoke_signed(
+                &transfer_ix,
+                &[
+                    ctx.accounts.bonus_pool.to_account_info(),
+                    ctx.accounts.bettor.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                signer_seeds,
+            )?;
+
+            let platform = &mut ctx.accounts.platform;
+            platform.total_bonus_paid = platform.total_bonus_paid.checked_add(bonus).unwrap();
+        }
+
+        // The referrer earns a share of the platform fee carried by this
+        // payout, paid from the part of the fee reserved for referrers
+        // whether or not the rest has been collected yet
+        let referrer = ctx.accounts.user_profile.referrer;
+        let referral_fee_bps = ctx.accounts.platform.referral_fee_bps;
+        if let Some(referrer) = referrer.filter(|_| referral_fee_bps > 0) {
+            let referrer_profile = ctx.accounts.referrer_profile
+                .as_mut()
+                .ok_or(PredictionError::ReferrerRequired)?;
+            require!(referrer_profile.owner == referrer, PredictionError::InvalidReferrer);
+
+            let reserved = market.referral_reserve(referral_fee_bps).saturating_sub(market.referral_paid);
+            let reward = ((platform_fee as u128)
+                .checked_mul(amount as u128).unwrap()
+                .checked_div(winning_pool as u128).unwrap()
+                .checked_mul(referral_fee_bps as u128).unwrap()
+                .checked_div(10000).unwrap() as u64)
+                .min(reserved);
+
+            if reward > 0 {
+                let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+                    &ctx.accounts.vault.key(),
+                    &referrer_profile.key(),
+                    reward,
+                );
+
+                anchor_lang::solana_program::program::invoke_signed(
+                    &transfer_ix,
+                    &[
+                        ctx.accounts.vault.to_account_info(),
+                        referrer_profile.to_account_info(),
+                        ctx.accounts.system_program.to_account_info(),
+                    ],
+                    signer_seeds,
+                )?;
+
+                referrer_profile.referral_rewards = referrer_profile.referral_rewards.checked_add(reward).unwrap();
+                referrer_profile.total_referral_earned = referrer_profile.total_referral_earned.checked_add(reward).unwrap();
+                let market = &mut ctx.accounts.market;
+                market.referral_paid = market.referral_paid.checked_add(reward).unwrap();
+            }
+        }
+
+        // The payout itself was realised when the bet settled; the bonus is
+        // only known once it is paid
+        if bonus > 0 {
+            ctx.accounts.user_profile.record_bonus(bonus, season_id);
+        }
+
+        emit!(WinningsClaimed {
+            market: market_key,
+            claimant: ctx.accounts.bettor.key(),
+            position_mint: ctx.accounts.position_mint.key(),
+            redeemed: amount,
+            winnings,
+            bonus,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Permissionless crank: records a resolved bet's result on the bettor's
+    // profile and, for battle bets, on its card (freeing the battle slot), so
+    // a losing bettor cannot keep their PnL, streak or card record clean by
+    // never claiming or closing
+    pub fn settle_bet(ctx: Context<SettleBet>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(market.resolved, PredictionError::MarketNotResolved);
+
+        let bet = &mut ctx.accounts.bet;
+        require!(!bet.settled, PredictionError::BetAlreadySettled);
+        settle_bet_result(bet, ctx.accounts.card.as_mut(), &mut ctx.accounts.user_profile, market)
+    }
+
+    // Reclaim a Bet's rent after resolution, settling it first if nobody has
+    pub fn close_bet(ctx: Context<CloseBet>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(market.resolved, PredictionError::MarketNotResolved);
+
+        let profile = &mut ctx.accounts.user_profile;
+        profile.init_if_new(ctx.accounts.bettor.key(), ctx.bumps.user_profile);
+        settle_bet_result(&mut ctx.accounts.bet, ctx.accounts.card.as_mut(), profile, market)
+    }
+
+    pub fn collect_platform_fee(ctx: Context<CollectPlatformFee>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(market.resolved, PredictionError::MarketNotResolved);
+        require!(!market.fee_collected, PredictionError::FeeAlreadyCollected);
+        require!(market.outcome.is_some(), PredictionError::MarketVoided);
+
+        let platform = &ctx.accounts.platform;
+        require!(
+            ctx.accounts.authority.key() == platform.authority,
+            PredictionError::Unauthorized
         );
 
-        // Calculate platform fee
-        let total_pool = market.total_yes_amount.checked_add(market.total_no_amount).unwrap();
+        // Calculate platform fee, less the referrers' share, which stays in
+        // the vault for referred winners who have not claimed yet
+        let total_pool = market.total_pool();
+        let referral_share = market.referral_reserve(platform.referral_fee_bps).max(market.referral_paid);
         let platform_fee = total_pool
             .checked_mul(PLATFORM_FEE_BPS).unwrap()
-            .checked_div(10000).unwrap();
+            .checked_div(10000).unwrap()
+            .saturating_sub(referral_share);
+        market.fee_collected = true;
+
+        // While a season runs, part of the fee funds its leaderboard prizes
+        let leaderboard_share = if platform.current_season != 0 {
+            platform_fee
+                .checked_mul(platform.leaderboard_fee_bps as u64).unwrap()
+                .checked_div(10000).unwrap()
+        } else {
+            0
+        };
+        let treasury_fee = platform_fee.checked_sub(leaderboard_share).unwrap();
 
         // Transfer fee from vault to treasury
         let market_key = market.key();
@@ -431,7 +1066,7 @@ pub mod prediction {
         let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
             &ctx.accounts.vault.key(),
             &platform.treasury,
-            platform_fee,
+            treasury_fee,
         );
 
         anchor_lang::solana_program::program::invoke_signed(
@@ -444,15 +1079,435 @@ pub mod prediction {
             signer_seeds,
         )?;
 
+        if leaderboard_share > 0 {
+            let leaderboard = ctx.accounts.leaderboard
+                .as_mut()
+                .ok_or(PredictionError::LeaderboardRequired)?;
+            require!(
+                leaderboard.season_id == platform.current_season,
+                PredictionError::LeaderboardRequired
+            );
+
+            let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.vault.key(),
+                &leaderboard.key(),
+                leaderboard_share,
+            );
+
+            anchor_lang::solana_program::program::invoke_signed(
+                &transfer_ix,
+                &[
+                    ctx.accounts.vault.to_account_info(),
+                    leaderboard.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                signer_seeds,
+            )?;
+
+            leaderboard.prize_pool = leaderboard.prize_pool.checked_add(leaderboard_share).unwrap();
+        }
+
+        emit!(FeeCollected {
+            market: market_key,
+            treasury: platform.treasury,
+            amount: platform_fee,
+            total_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_card_config(
+        ctx: Context<InitializeCardConfig>,
+        minter: Pubkey,
+        randomness_oracle: Pubkey,
+        mint_price: u64,
+        tiers: Vec<RarityTier>,
+        uri_template: String,
+        battle_cooldown: i64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.platform.authority,
+            PredictionError::Unauthorized
+        );
+        validate_rarity_tiers(&tiers)?;
+        require!(uri_template.len() <= MAX_URI_TEMPLATE_LEN, PredictionError::UriTooLong);
+        require!(battle_cooldown >= 0, PredictionError::InvalidCooldown);
+
+        let card_config = &mut ctx.accounts.card_config;
+        card_config.authority = ctx.accounts.authority.key();
+        card_config.minter = minter;
+        card_config.randomness_oracle = randomness_oracle;
+        card_config.mint_price = mint_price;
+        card_config.tiers = tiers;
+        card_config.uri_template = uri_template;
+        card_config.battle_cooldown = battle_cooldown;
+        card_config.level_curve = LevelCurve::default();
+        card_config.current_season = 0;
+        card_config.season_active = false;
+        card_config.bump = ctx.bumps.card_config;
+
+        Ok(())
+    }
+
+    pub fn update_card_config(
+        ctx: Context<UpdateCardConfig>,
+        minter: Pubkey,
+        randomness_oracle: Pubkey,
+        mint_price: u64,
+        tiers: Vec<RarityTier>,
+        uri_template: String,
+        battle_cooldown: i64,
+    ) -> Result<()> {
+        let card_config = &mut ctx.accounts.card_config;
+        require!(
+            ctx.accounts.authority.key() == card_config.authority,
+            PredictionError::Unauthorized
+        );
+        validate_rarity_tiers(&tiers)?;
+        require!(uri_template.len() <= MAX_URI_TEMPLATE_LEN, PredictionError::UriTooLong);
+        require!(battle_cooldown >= 0, PredictionError::InvalidCooldown);
+
+        card_config.minter = minter;
+        card_config.randomness_oracle = randomness_oracle;
+        card_config.mint_price = mint_price;
+        card_config.tiers = tiers;
+        card_config.uri_template = uri_template;
+        card_config.battle_cooldown = battle_cooldown;
+
+        Ok(())
+    }
+
+    pub fn set_level_curve(
+        ctx: Context<UpdateCardConfig>,
+        level_curve: LevelCurve,
+    ) -> Result<()> {
+        let card_config = &mut ctx.accounts.card_config;
+        require!(
+            ctx.accounts.authority.key() == card_config.authority,
+            PredictionError::Unauthorized
+        );
+        require!(level_curve.base_xp > 0, PredictionError::InvalidLevelCurve);
+
+        card_config.level_curve = level_curve;
+
+        Ok(())
+    }
+
+    // Opens the next card season; cards can only be minted while a season
+    // is active, and each rarity is capped at `supply_caps[rarity]` cards
+    pub fn start_season(
+        ctx: Context<StartSeason>,
+        season_id: u32,
+        supply_caps: Vec<u32>,
+        end_time: i64,
+    ) -> Result<()> {
+        let card_config = &mut ctx.accounts.card_config;
+        require!(
+            ctx.accounts.authority.key() == card_config.authority,
+            PredictionError::Unauthorized
+        );
+        require!(!card_config.season_active, PredictionError::SeasonActive);
+        require!(
+            season_id == card_config.current_season.checked_add(1).ok_or(PredictionError::MathOverflow)?,
+            PredictionError::InvalidSeason
+        );
+        require!(supply_caps.len() == card_config.tiers.len(), PredictionError::InvalidSeason);
+        let now = Clock::get()?.unix_timestamp;
+        require!(end_time > now, PredictionError::InvalidSeason);
+
+        card_config.current_season = season_id;
+        card_config.season_active = true;
+        ctx.accounts.platform.current_season = season_id;
+
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        leaderboard.season_id = season_id;
+        leaderboard.entries = Vec::new();
+        leaderboard.prize_pool = 0;
+        leaderboard.distributed = false;
+        leaderboard.bump = ctx.bumps.leaderboard;
+
+        let season = &mut ctx.accounts.season;
+        season.id = season_id;
+        season.minted = vec![0; supply_caps.len()];
+        season.supply_caps = supply_caps;
+        season.start_time = now;
+        season.end_time = end_time;
+        season.start_volume = ctx.accounts.platform.total_volume;
+        season.ended = false;
+        season.ended_at = 0;
+        season.end_volume = 0;
+        season.end_bonus_paid = 0;
+        season.bump = ctx.bumps.season;
+
+        Ok(())
+    }
+
+    // Closes the current season and snapshots platform totals for
+    // season-scoped rewards. Permissionless once end_time has passed;
+    // the card authority may end a season early.
+    pub fn end_season(ctx: Context<EndSeason>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let season = &mut ctx.accounts.season;
+        require!(!season.ended, PredictionError::SeasonNotActive);
+        require!(
+            now >= season.end_time || ctx.accounts.caller.key() == ctx.accounts.card_config.authority,
+            PredictionError::SeasonNotOver
+        );
+
+        season.ended = true;
+        season.ended_at = now;
+        season.end_volume = ctx.accounts.platform.total_volume;
+        season.end_bonus_paid = ctx.accounts.platform.total_bonus_paid;
+
+        ctx.accounts.card_config.season_active = false;
+        ctx.accounts.platform.current_season = 0;
+
+        Ok(())
+    }
+
+    // Permissionless: ranks (or re-ranks) a wallet's season PnL on the season
+    // leaderboard, up until the prizes are distributed
+    pub fn submit_leaderboard_entry(ctx: Context<SubmitLeaderboardEntry>) -> Result<()> {
+        let profile = &ctx.accounts.user_profile;
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        require!(!leaderboard.distributed, PredictionError::PrizesAlreadyDistributed);
+        let pnl = profile
+            .season_pnl_for(leaderboard.season_id)
+            .ok_or(PredictionError::InvalidSeason)?;
+
+        leaderboard.submit(profile.owner, pnl);
+
+        Ok(())
+    }
+
+    // Pays the prize pool to the ranked wallets once the season's settlement
+    // period has passed, re-ranking every entry on its profile's current
+    // season PnL so losses settled after submission count.
+    // remaining_accounts: [user_profile, wallet] for each of Leaderboard.entries
+    pub fn distribute_leaderboard_prizes<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeLeaderboardPrizes<'info>>,
+    ) -> Result<()> {
+        let season = &ctx.accounts.season;
+        require!(
+            season.ended
+                && Clock::get()?.unix_timestamp
+                    >= season.ended_at.saturating_add(ctx.accounts.platform.leaderboard_settlement_period),
+            PredictionError::SeasonNotOver
+        );
+        require!(
+            ctx.accounts.treasury.key() == ctx.accounts.platform.treasury,
+            PredictionError::Unauthorized
+        );
+
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        require!(!leaderboard.distributed, PredictionError::PrizesAlreadyDistributed);
+        require!(
+            ctx.remaining_accounts.len() == leaderboard.entries.len() * 2,
+            PredictionError::InvalidPosition
+        );
+
+        let mut ranked = Vec::with_capacity(leaderboard.entries.len());
+        for (entry, accounts) in leaderboard.entries.iter().zip(ctx.remaining_accounts.chunks(2)) {
+            let profile = Account::<UserProfile>::try_from(&accounts[0])?;
+            let wallet = &accounts[1];
+            require!(
+                profile.owner == entry.owner && wallet.key() == entry.owner,
+                PredictionError::InvalidPosition
+            );
+            let pnl = profile.season_pnl_for(leaderboard.season_id).unwrap_or(0);
+            if pnl > 0 {
+                ranked.push((pnl, wallet));
+            }
+        }
+        // Stable, so ties keep their submission order
+        ranked.sort_by_key(|r| std::cmp::Reverse(r.0));
+
+        let prize_pool = leaderboard.prize_pool;
+        let leaderboard_info = leaderboard.to_account_info();
+        let mut paid: u64 = 0;
+        for (rank, (_, wallet)) in ranked.into_iter().enumerate() {
+            let prize = prize_pool
+                .checked_mul(LEADERBOARD_PRIZE_BPS[rank]).unwrap()
+                .checked_div(10000).unwrap();
+            leaderboard_info.sub_lamports(prize)?;
+            wallet.add_lamports(prize)?;
+            paid = paid.checked_add(prize).unwrap();
+        }
+
+        // Shares of empty ranks and rounding dust go back to the treasury
+        let unclaimed = prize_pool.checked_sub(paid).unwrap();
+        leaderboard_info.sub_lamports(unclaimed)?;
+        ctx.accounts.treasury.add_lamports(unclaimed)?;
+
+        leaderboard.prize_pool = 0;
+        leaderboard.distributed = true;
+
+        Ok(())
+    }
+
+    // Returns a resolved market's creator stake once its dispute window has
+    // closed, and records whether the creator resolved it within the grace period
+    pub fn release_creator_stake(ctx: Context<ReleaseCreatorStake>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(market.resolved, PredictionError::MarketNotResolved);
+        require!(!market.stake_released, PredictionError::StakeAlreadyReleased);
+        require!(
+            Clock::get()?.unix_timestamp >= market.resolved_at.saturating_add(DISPUTE_WINDOW),
+            PredictionError::DisputeWindowOpen
+        );
+        require!(market.dispute != DisputeStatus::Open, PredictionError::DisputeOpen);
+        market.stake_released = true;
+
+        // Only manual markets are resolved by their creator, and expired ones
+        // were already counted as cancelled. An empty market proves nothing,
+        // so it cannot earn an on-time resolution.
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        if market.oracle_source == OracleSource::Manual && market.outcome.is_some() {
+            if market.resolved_at > market.resolution_deadline {
+                creator_profile.resolved_late = creator_profile.resolved_late.checked_add(1).unwrap();
+            } else if market.total_pool() > 0 && market.dispute != DisputeStatus::Overturned {
+                creator_profile.resolved_on_time = creator_profile.resolved_on_time.checked_add(1).unwrap();
+            }
+        }
+
+        // A bond still held here was not slashed, so it goes back with the stake
+        let stake = market.creator_stake.checked_add(market.creator_bond).unwrap();
+        market.creator_bond = 0;
+        creator_profile.locked_stake = creator_profile.locked_stake.checked_sub(stake).unwrap();
+        creator_profile.sub_lamports(stake)?;
+        ctx.accounts.creator.add_lamports(stake)?;
+
+        Ok(())
+    }
+
+    // Platform authority opens a dispute against a market's resolution
+    // within its dispute window, freezing the creator's stake
+    pub fn flag_market_dispute(ctx: Context<FlagMarketDispute>) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.platform.authority,
+            PredictionError::Unauthorized
+        );
+        let market = &mut ctx.accounts.market;
+        require!(market.resolved, PredictionError::MarketNotResolved);
+        // Oracles and expiry are not the creator's call
+        require!(
+            market.oracle_source == OracleSource::Manual && market.outcome.is_some(),
+            PredictionError::NotCreatorResolved
+        );
+        require!(market.dispute == DisputeStatus::None, PredictionError::MarketAlreadyDisputed);
+        require!(
+            Clock::get()?.unix_timestamp < market.resolved_at.saturating_add(DISPUTE_WINDOW),
+            PredictionError::DisputeWindowClosed
+        );
+        market.dispute = DisputeStatus::Open;
+
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        creator_profile.disputed = creator_profile.disputed.checked_add(1).unwrap();
+
+        Ok(())
+    }
+
+    // Platform authority rules on an open dispute, which unfreezes the stake.
+    // An overturned resolution slashes the creator's bond to the treasury:
+    // winners may already have claimed under the disputed outcome, so the
+    // pool cannot be paid out again.
+    pub fn rule_market_dispute(
+        ctx: Context<RuleMarketDispute>,
+        overturned: bool,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.platform.authority,
+            PredictionError::Unauthorized
+        );
+        let market = &mut ctx.accounts.market;
+        require!(market.dispute == DisputeStatus::Open, PredictionError::DisputeNotOpen);
+
+        if overturned {
+            require!(
+                ctx.accounts.treasury.key() == ctx.accounts.platform.treasury,
+                PredictionError::Unauthorized
+            );
+            market.dispute = DisputeStatus::Overturned;
+
+            let creator_profile = &mut ctx.accounts.creator_profile;
+            let bond = market.creator_bond;
+            if bond > 0 {
+                creator_profile.locked_stake = creator_profile.locked_stake.checked_sub(bond).unwrap();
+                creator_profile.sub_lamports(bond)?;
+                ctx.accounts.treasury.add_lamports(bond)?;
+                market.creator_bond = 0;
+            }
+            creator_profile.overturned = creator_profile.overturned.checked_add(1).unwrap();
+        } else {
+            market.dispute = DisputeStatus::Upheld;
+        }
+
         Ok(())
     }
 
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        let profile = &mut ctx.accounts.user_profile;
+        let rewards = profile.referral_rewards;
+        require!(rewards > 0, PredictionError::NoReferralRewards);
+
+        // Rewards are held as lamports on the profile account itself
+        profile.referral_rewards = 0;
+        profile.sub_lamports(rewards)?;
+        ctx.accounts.owner.add_lamports(rewards)?;
+
+        Ok(())
+    }
+
+    pub fn level_up(ctx: Context<LevelUp>) -> Result<()> {
+        let curve = ctx.accounts.card_config.level_curve;
+        let card = &mut ctx.accounts.card;
+
+        require!(
+            ctx.accounts.card_token_account.amount == 1,
+            PredictionError::NotCardOwner
+        );
+        require!(card.level < curve.max_level, PredictionError::MaxLevelReached);
+
+        // Spend the XP for the next level
+        let required = curve.xp_for_next_level(card.level);
+        require!(card.experience >= required, PredictionError::NotEnoughExperience);
+        card.experience -= required;
+
+        card.level += 1;
+        card.power = card.power.saturating_add(curve.power_per_level);
+        card.multiplier = card.multiplier.checked_add(curve.multiplier_per_level).unwrap();
+
+        emit!(CardLeveledUp {
+            card: card.key(),
+            level: card.level,
+            power: card.power,
+            multiplier: card.multiplier,
+            remaining_experience: card.experience,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        emit_card_stats_updated(card)
+    }
+
     pub fn mint_card(
         ctx: Context<MintCard>,
         power: u8,
         rarity: u8,
         multiplier: u64,
     ) -> Result<()> {
+        let card_config = &ctx.accounts.card_config;
+
+        // Hand-picked stats are reserved for the configured minter; everyone
+        // else goes through request_card_mint and gets rolled stats
+        require!(
+            ctx.accounts.payer.key() == card_config.minter,
+            PredictionError::Unauthorized
+        );
+        card_config.validate_stats(rarity, power, multiplier)?;
+        ctx.accounts.season.reserve(rarity)?;
+
         // Mint 1 token to the owner's token account (NFT standard: supply = 1)
         let cpi_accounts = MintTo {
             mint: ctx.accounts.mint.to_account_info(),
@@ -463,6 +1518,33 @@ pub mod prediction {
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
         token::mint_to(cpi_ctx, 1)?;
 
+        create_card_metadata(
+            card_config,
+            rarity,
+            ctx.bumps.card_authority,
+            ctx.accounts.token_metadata_program.to_account_info(),
+            CreateMetadataAccountsV3 {
+                metadata: ctx.accounts.metadata.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                mint_authority: ctx.accounts.payer.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                update_authority: ctx.accounts.card_authority.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+            },
+            CreateMasterEditionV3 {
+                edition: ctx.accounts.master_edition.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                update_authority: ctx.accounts.card_authority.to_account_info(),
+                mint_authority: ctx.accounts.payer.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                metadata: ctx.accounts.metadata.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+            },
+        )?;
+
         // Store card metadata on-chain
         let card = &mut ctx.accounts.card;
         card.mint = ctx.accounts.mint.key();
@@ -472,143 +1554,3022 @@ pub mod prediction {
         card.multiplier = multiplier;
         card.wins = 0;
         card.losses = 0;
+        card.active_battles = 0;
+        card.last_settled_at = 0;
+        card.experience = 0;
+        card.level = 0;
+        card.season_id = ctx.accounts.season.id;
         card.bump = ctx.bumps.card;
 
+        emit_card_minted(card, false)?;
+
         Ok(())
     }
 
-    pub fn battle(
-        ctx: Context<Battle>,
-        amount: u64,
-        prediction: bool,
+    pub fn request_card_mint(
+        ctx: Context<RequestCardMint>,
+        seed: [u8; 32],
     ) -> Result<()> {
-        require!(amount > 0, PredictionError::InvalidAmount);
-        
-        let market = &mut ctx.accounts.market;
-        let card = &ctx.accounts.card;
-        
-        // Verify card ownership via token account
-        require!(
-            ctx.accounts.card_token_account.amount == 1,
-            PredictionError::NotCardOwner
-        );
+        let card_config = &ctx.accounts.card_config;
+        let season = &ctx.accounts.season;
         require!(
-            ctx.accounts.card_token_account.owner == ctx.accounts.player.key(),
-            PredictionError::NotCardOwner
+            season.is_open(Clock::get()?.unix_timestamp) && season.has_any_supply(),
+            PredictionError::SeasonNotActive
         );
-        
-        require!(!market.resolved, PredictionError::MarketAlreadyResolved);
-        require!(Clock::get()?.unix_timestamp < market.end_time, PredictionError::MarketEnded);
 
-        // Transfer SOL from player to market vault
-        let cpi_context = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            system_program::Transfer {
-                from: ctx.accounts.player.to_account_info(),
-                to: ctx.accounts.vault.to_account_info(),
-            },
-        );
-        system_program::transfer(cpi_context, amount)?;
+        // Paid mint; the configured minter requests for free. The price is
+        // escrowed on the request until the card is minted, so a request the
+        // season can no longer fill is refunded by cancel_card_mint_request
+        let mut paid = 0;
+        if ctx.accounts.owner.key() != card_config.minter {
+            require!(card_config.mint_price > 0, PredictionError::Unauthorized);
 
-        // Update market totals
-        if prediction {
-            market.total_yes_amount = market.total_yes_amount.checked_add(amount).unwrap();
-        } else {
-            market.total_no_amount = market.total_no_amount.checked_add(amount).unwrap();
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.request.to_account_info(),
+                },
+            );
+            system_program::transfer(cpi_context, card_config.mint_price)?;
+            paid = card_config.mint_price;
         }
 
-        // Initialize or update bet account with card multiplier
-        let bet = &mut ctx.accounts.bet;
-        bet.market = market.key();
-        bet.bettor = ctx.accounts.player.key();
-        bet.amount = bet.amount.checked_add(amount).unwrap();
-        bet.prediction = prediction;
-        bet.claimed = false;
-        bet.card_mint = Some(card.mint);
-        bet.card_multiplier = card.multiplier;
+        let request = &mut ctx.accounts.request;
+        request.owner = ctx.accounts.owner.key();
+        request.seed = seed;
+        request.randomness = ctx.accounts.randomness.key();
+        request.requested_slot = Clock::get()?.slot;
+        request.fusion = None;
+        request.season_id = season.id;
+        request.paid = paid;
+        request.bump = ctx.bumps.request;
 
-        // Update platform volume
-        let platform = &mut ctx.accounts.platform;
-        platform.total_volume = platform.total_volume.checked_add(amount).unwrap();
+        let randomness = &mut ctx.accounts.randomness;
+        randomness.consumer = request.key();
+        randomness.oracle = card_config.randomness_oracle;
+        randomness.seed = seed;
+        randomness.value = [0; 32];
+        randomness.fulfilled_slot = 0;
+        randomness.bump = ctx.bumps.randomness;
 
         Ok(())
     }
 
-    pub fn update_card_stats(
-        ctx: Context<UpdateCardStats>,
-        won: bool,
+    // Burns FUSION_CARD_COUNT cards of one rarity and requests randomness for a
+    // card of the next tier. The cards are burned up front so the roll cannot
+    // be previewed before committing them; fulfill_card_mint mints the result.
+    // remaining_accounts: [card, card mint, card token account] per burned card
+    pub fn fuse_cards<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FuseCards<'info>>,
+        seed: [u8; 32],
     ) -> Result<()> {
-        let card = &mut ctx.accounts.card;
-        
-        // Verify card ownership
+        let card_config = &ctx.accounts.card_config;
         require!(
-            ctx.accounts.card_token_account.amount == 1,
-            PredictionError::NotCardOwner
+            ctx.remaining_accounts.len() == FUSION_CARD_COUNT * 3,
+            PredictionError::InvalidFusion
         );
+
+        let mut fusion: Option<FusedCards> = None;
+        let mut burned_mints: Vec<Pubkey> = Vec::with_capacity(FUSION_CARD_COUNT);
+        for accounts in ctx.remaining_accounts.chunks(3) {
+            let card: Account<'info, Card> = Account::try_from(&accounts[0])?;
+            let mint: Account<'info, Mint> = Account::try_from(&accounts[1])?;
+            let token_account: Account<'info, TokenAccount> = Account::try_from(&accounts[2])?;
+
+            require!(card.mint == mint.key(), PredictionError::CardMismatch);
+            require!(!burned_mints.contains(&card.mint), PredictionError::InvalidFusion);
+            require!(
+                token_account.mint == card.mint
+                    && token_account.owner == ctx.accounts.owner.key()
+                    && token_account.amount == 1,
+                PredictionError::NotCardOwner
+            );
+            require!(card.active_battles == 0, PredictionError::CardInBattle);
+            burned_mints.push(card.mint);
+
+            let combined = match fusion {
+                None => FusedCards {
+                    season_id: 0,
+                    rarity: card.rarity,
+                    power: card.power,
+                    multiplier: card.multiplier,
+                    wins: card.wins,
+                    losses: card.losses,
+                    experience: card.experience,
+                },
+                Some(combined) => {
+                    require!(card.rarity == combined.rarity, PredictionError::InvalidFusion);
+                    FusedCards {
+                        season_id: combined.season_id,
+                        rarity: combined.rarity,
+                        power: combined.power.max(card.power),
+                        multiplier: combined.multiplier.max(card.multiplier),
+                        wins: combined.wins.checked_add(card.wins).ok_or(PredictionError::MathOverflow)?,
+                        losses: combined.losses.checked_add(card.losses).ok_or(PredictionError::MathOverflow)?,
+                        experience: combined.experience
+                            .checked_add(card.experience)
+                            .ok_or(PredictionError::MathOverflow)?,
+                    }
+                }
+            };
+            fusion = Some(combined);
+
+            // Burn the NFT, return the token account's rent, then close the Card
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: mint.to_account_info(),
+                    from: token_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            );
+            token::burn(cpi_ctx, 1)?;
+
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: token_account.to_account_info(),
+                    destination: ctx.accounts.owner.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            );
+            token::close_account(cpi_ctx)?;
+
+            card.close(ctx.accounts.owner.to_account_info())?;
+        }
+
+        // The result is one tier above the burned cards
+        let mut fusion = fusion.ok_or(PredictionError::InvalidFusion)?;
+        fusion.rarity = fusion.rarity
+            .checked_add(1)
+            .filter(|rarity| (*rarity as usize) < card_config.tiers.len())
+            .ok_or(PredictionError::MaxRarityReached)?;
+        ctx.accounts.season.reserve(fusion.rarity)?;
+        fusion.season_id = ctx.accounts.season.id;
+
+        let request = &mut ctx.accounts.request;
+        request.owner = ctx.accounts.owner.key();
+        request.seed = seed;
+        request.randomness = ctx.accounts.randomness.key();
+        request.requested_slot = Clock::get()?.slot;
+        request.fusion = Some(fusion);
+        request.season_id = fusion.season_id;
+        request.paid = 0;
+        request.bump = ctx.bumps.request;
+
+        let randomness = &mut ctx.accounts.randomness;
+        randomness.consumer = request.key();
+        randomness.oracle = card_config.randomness_oracle;
+        randomness.seed = seed;
+        randomness.value = [0; 32];
+        randomness.fulfilled_slot = 0;
+        randomness.bump = ctx.bumps.randomness;
+
+        Ok(())
+    }
+
+    // Called by the randomness oracle (or the local test oracle) once the
+    // request's VRF output is available
+    pub fn fulfill_randomness(
+        ctx: Context<FulfillRandomness>,
+        value: [u8; 32],
+    ) -> Result<()> {
+        let randomness = &mut ctx.accounts.randomness;
         require!(
-            ctx.accounts.card_token_account.owner == ctx.accounts.owner.key(),
-            PredictionError::NotCardOwner
+            ctx.accounts.oracle.key() == randomness.oracle,
+            PredictionError::Unauthorized
         );
-        
-        if won {
-            card.wins = card.wins.checked_add(1).unwrap();
-        } else {
-            card.losses = card.losses.checked_add(1).unwrap();
-        }
-        
+        require!(randomness.fulfilled_slot == 0, PredictionError::RandomnessAlreadyFulfilled);
+
+        randomness.value = value;
+        randomness.fulfilled_slot = Clock::get()?.slot;
+
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct InitializePlatform<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + Platform::INIT_SPACE,
-        seeds = [b"platform"],
-        bump
-    )]
+    pub fn fulfill_card_mint(ctx: Context<FulfillCardMint>) -> Result<()> {
+        let request = &ctx.accounts.request;
+        let randomness = &ctx.accounts.randomness;
+        require!(randomness.fulfilled_slot > 0, PredictionError::RandomnessNotFulfilled);
+
+        // Bind the oracle output to this specific request before rolling
+        let digest = hashv(&[
+            &randomness.value,
+            &request.seed,
+            request.key().as_ref(),
+        ]).to_bytes();
+        // Fusions reserved their supply when the cards were burned
+        let (rarity, power, multiplier, season_id) = match &request.fusion {
+            Some(fusion) => {
+                let (rarity, power, multiplier) = ctx.accounts.card_config.roll_fusion(fusion, &digest)?;
+                (rarity, power, multiplier, fusion.season_id)
+            }
+            None => {
+                let season = &mut ctx.accounts.season;
+                let (rarity, power, multiplier) = ctx.accounts.card_config.roll(&digest, season)?;
+                season.reserve(rarity)?;
+                (rarity, power, multiplier, season.id)
+            }
+        };
+        let carried = request.fusion;
+
+        // Release the escrowed mint price now that the card is being minted
+        if request.paid > 0 {
+            require!(
+                ctx.accounts.treasury.key() == ctx.accounts.platform.treasury,
+                PredictionError::Unauthorized
+            );
+            ctx.accounts.request.sub_lamports(request.paid)?;
+            ctx.accounts.treasury.add_lamports(request.paid)?;
+        }
+
+        // Mint 1 token to the owner's token account (NFT standard: supply = 1)
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.token_account.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::mint_to(cpi_ctx, 1)?;
+
+        create_card_metadata(
+            &ctx.accounts.card_config,
+            rarity,
+            ctx.bumps.card_authority,
+            ctx.accounts.token_metadata_program.to_account_info(),
+            CreateMetadataAccountsV3 {
+                metadata: ctx.accounts.metadata.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                mint_authority: ctx.accounts.owner.to_account_info(),
+                payer: ctx.accounts.owner.to_account_info(),
+                update_authority: ctx.accounts.card_authority.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+            },
+            CreateMasterEditionV3 {
+                edition: ctx.accounts.master_edition.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                update_authority: ctx.accounts.card_authority.to_account_info(),
+                mint_authority: ctx.accounts.owner.to_account_info(),
+                payer: ctx.accounts.owner.to_account_info(),
+                metadata: ctx.accounts.metadata.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+            },
+        )?;
+
+        // Store card metadata on-chain
+        let card = &mut ctx.accounts.card;
+        card.mint = ctx.accounts.mint.key();
+        card.owner = ctx.accounts.owner.key();
+        card.power = power;
+        card.rarity = rarity;
+        card.multiplier = multiplier;
+        card.wins = carried.map_or(0, |fusion| fusion.wins);
+        card.losses = carried.map_or(0, |fusion| fusion.losses);
+        card.active_battles = 0;
+        card.last_settled_at = 0;
+        card.experience = carried.map_or(0, |fusion| fusion.experience);
+        card.level = 0;
+        card.season_id = season_id;
+        card.bump = ctx.bumps.card;
+
+        emit_card_minted(card, carried.is_some())?;
+
+        Ok(())
+    }
+
+    // Closes a paid request its season can no longer fill, refunding the
+    // escrowed mint price and rent. Fusions reserved their supply up front
+    // and always stay fillable. Once the oracle has answered, the owner could
+    // preview the roll, so only unanswered requests can be cancelled.
+    pub fn cancel_card_mint_request(ctx: Context<CancelCardMintRequest>) -> Result<()> {
+        let season = &ctx.accounts.season;
+        require!(ctx.accounts.request.fusion.is_none(), PredictionError::MintRequestFillable);
+        require!(
+            ctx.accounts.randomness.fulfilled_slot == 0,
+            PredictionError::RandomnessAlreadyFulfilled
+        );
+        require!(
+            !(season.is_open(Clock::get()?.unix_timestamp) && season.has_any_supply()),
+            PredictionError::MintRequestFillable
+        );
+
+        Ok(())
+    }
+
+    // Permissionless: point Card.owner at whoever currently holds the card token
+    pub fn sync_card_owner(ctx: Context<SyncCardOwner>) -> Result<()> {
+        require!(
+            ctx.accounts.card_token_account.amount == 1,
+            PredictionError::NotCardOwner
+        );
+
+        let card = &mut ctx.accounts.card;
+        card.owner = ctx.accounts.card_token_account.owner;
+
+        Ok(())
+    }
+
+    pub fn battle(
+        ctx: Context<Battle>,
+        amount: u64,
+        prediction: bool,
+    ) -> Result<()> {
+        require!(amount > 0, PredictionError::InvalidAmount);
+    
+        let market = &mut ctx.accounts.market;
+        let card = &mut ctx.accounts.card;
+    
+        // The token holder is the single source of truth for card ownership
+        require!(
+            ctx.accounts.card_token_account.amount == 1,
+            PredictionError::NotCardOwner
+        );
+        match &ctx.accounts.card_listing {
+            // An escrowed card plays for its current renter; the lender stays the owner
+            Some(listing) => {
+                require!(
+                    ctx.accounts.card_token_account.owner == listing.key() && listing.mint == card.mint,
+                    PredictionError::NotCardOwner
+                );
+                require!(
+                    listing.renter == Some(ctx.accounts.player.key())
+                        && Clock::get()?.unix_timestamp < listing.rented_until,
+                    PredictionError::NotCardRenter
+                );
+            }
+            None => {
+                require!(
+                    ctx.accounts.card_token_account.owner == ctx.accounts.player.key(),
+                    PredictionError::NotCardOwner
+                );
+                card.owner = ctx.accounts.player.key();
+            }
+        }
+
+        // A bet carries one card; a new battle counts against the card's
+        // per-rarity concurrency limit and must wait out the cooldown
+        let bet_card = ctx.accounts.bet.card_mint;
+        require!(
+            bet_card.is_none() || bet_card == Some(card.mint),
+            PredictionError::CardAlreadyAttached
+        );
+        if bet_card.is_none() {
+            reserve_battle_slot(card, &ctx.accounts.card_config)?;
+        }
+    
+        require!(!market.resolved, PredictionError::MarketAlreadyResolved);
+        require!(Clock::get()?.unix_timestamp < market.end_time, PredictionError::MarketEnded);
+
+        // Transfer SOL from player to market vault
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.player.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_context, amount)?;
+
+        // Mint transferable position tokens for the chosen side
+        let market_key = market.key();
+        let bump = ctx.bumps.vault;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            market_key.as_ref(),
+            &[bump],
+        ]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.position_mint.to_account_info(),
+                to: ctx.accounts.position_token_account.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::mint_to(cpi_ctx, amount)?;
+
+        // Update market totals
+        if prediction {
+            market.total_yes_amount = market.total_yes_amount.checked_add(amount).unwrap();
+        } else {
+            market.total_no_amount = market.total_no_amount.checked_add(amount).unwrap();
+        }
+
+        // Initialize or update bet account with card multiplier
+        let bet = &mut ctx.accounts.bet;
+        let first_bet = bet.market == Pubkey::default();
+        bet.market = market.key();
+        bet.bettor = ctx.accounts.player.key();
+        if prediction {
+            bet.yes_amount = bet.yes_amount.checked_add(amount).unwrap();
+            bet.card_yes_amount = bet.card_yes_amount.checked_add(amount).unwrap();
+        } else {
+            bet.no_amount = bet.no_amount.checked_add(amount).unwrap();
+            bet.card_no_amount = bet.card_no_amount.checked_add(amount).unwrap();
+        }
+        // The multiplier is fixed when the card joins the bet, so a later
+        // level-up cannot boost stake placed before it
+        if bet_card.is_none() {
+            bet.card_mint = Some(card.mint);
+            bet.card_multiplier = card.multiplier;
+        }
+
+        // Update platform volume
+        let platform = &mut ctx.accounts.platform;
+        platform.total_volume = platform.total_volume.checked_add(amount).unwrap();
+
+        let profile = &mut ctx.accounts.user_profile;
+        profile.init_if_new(ctx.accounts.player.key(), ctx.bumps.user_profile);
+        profile.attach_referrer(ctx.accounts.referrer_profile.as_deref())?;
+        profile.record_wager(amount, first_bet);
+        if profile.referrer.is_some() {
+            market.record_referred_stake(amount, prediction);
+        }
+
+        emit!(BetPlaced {
+            market: market.key(),
+            bettor: bet.bettor,
+            prediction,
+            amount,
+            card: Some(card.mint),
+            total_yes_amount: market.total_yes_amount,
+            total_no_amount: market.total_no_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Opens a head-to-head duel: the challenger escrows `stake` on one side
+    // of the market with a card and waits for an opponent to take the other
+    pub fn create_duel(
+        ctx: Context<CreateDuel>,
+        stake: u64,
+        prediction: bool,
+    ) -> Result<()> {
+        require!(stake > 0, PredictionError::InvalidAmount);
+
+        let market = &ctx.accounts.market;
+        require!(!market.resolved, PredictionError::MarketAlreadyResolved);
+        require!(Clock::get()?.unix_timestamp < market.end_time, PredictionError::MarketEnded);
+
+        let card = &mut ctx.accounts.card;
+        require!(
+            ctx.accounts.card_token_account.amount == 1
+                && ctx.accounts.card_token_account.owner == ctx.accounts.challenger.key(),
+            PredictionError::NotCardOwner
+        );
+        card.owner = ctx.accounts.challenger.key();
+        reserve_battle_slot(card, &ctx.accounts.card_config)?;
+
+        // Stakes sit in the duel account itself until settlement
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.challenger.to_account_info(),
+                to: ctx.accounts.duel.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_context, stake)?;
+
+        let duel = &mut ctx.accounts.duel;
+        duel.market = market.key();
+        duel.challenger = ctx.accounts.challenger.key();
+        duel.challenger_card = card.key();
+        duel.challenger_prediction = prediction;
+        duel.opponent = None;
+        duel.opponent_card = None;
+        duel.stake = stake;
+        duel.created_at = Clock::get()?.unix_timestamp;
+        duel.bump = ctx.bumps.duel;
+
+        emit!(DuelCreated {
+            duel: duel.key(),
+            market: duel.market,
+            challenger: duel.challenger,
+            card: duel.challenger_card,
+            prediction,
+            stake,
+            timestamp: duel.created_at,
+        });
+
+        Ok(())
+    }
+
+    // Takes the opposite side of an open duel with a matching stake
+    pub fn accept_duel(ctx: Context<AcceptDuel>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(!market.resolved, PredictionError::MarketAlreadyResolved);
+        require!(Clock::get()?.unix_timestamp < market.end_time, PredictionError::MarketEnded);
+
+        let duel = &ctx.accounts.duel;
+        require!(duel.opponent.is_none(), PredictionError::DuelAlreadyAccepted);
+        require!(
+            ctx.accounts.opponent.key() != duel.challenger,
+            PredictionError::Unauthorized
+        );
+
+        let card = &mut ctx.accounts.card;
+        require!(
+            ctx.accounts.card_token_account.amount == 1
+                && ctx.accounts.card_token_account.owner == ctx.accounts.opponent.key(),
+            PredictionError::NotCardOwner
+        );
+        card.owner = ctx.accounts.opponent.key();
+        reserve_battle_slot(card, &ctx.accounts.card_config)?;
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.opponent.to_account_info(),
+                to: ctx.accounts.duel.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_context, duel.stake)?;
+
+        let duel = &mut ctx.accounts.duel;
+        duel.opponent = Some(ctx.accounts.opponent.key());
+        duel.opponent_card = Some(card.key());
+
+        emit!(DuelAccepted {
+            duel: duel.key(),
+            market: duel.market,
+            opponent: ctx.accounts.opponent.key(),
+            card: card.key(),
+            prediction: !duel.challenger_prediction,
+            stake: duel.stake,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Withdraws a duel nobody has accepted; stake and rent go back to the challenger
+    pub fn cancel_duel(ctx: Context<CancelDuel>) -> Result<()> {
+        let duel = &ctx.accounts.duel;
+        require!(duel.opponent.is_none(), PredictionError::DuelAlreadyAccepted);
+        release_battle_slot(&mut ctx.accounts.challenger_card)?;
+
+        emit!(DuelCancelled {
+            duel: duel.key(),
+            challenger: duel.challenger,
+            stake: duel.stake,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Permissionless once the market resolves. The side that called the
+    // outcome takes both stakes. A voided market is a tie: the card with more
+    // power takes both stakes, and equal power refunds each player's own stake.
+    pub fn settle_duel(ctx: Context<SettleDuel>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(market.resolved, PredictionError::MarketNotResolved);
+
+        let duel = &ctx.accounts.duel;
+        let stake = duel.stake;
+        let challenger_card = &mut ctx.accounts.challenger_card;
+        let opponent_card = &mut ctx.accounts.opponent_card;
+
+        let challenger_won = match market.outcome {
+            Some(outcome) => Some(outcome == duel.challenger_prediction),
+            None if challenger_card.power != opponent_card.power => {
+                Some(challenger_card.power > opponent_card.power)
+            }
+            None => None,
+        };
+
+        let duel_info = ctx.accounts.duel.to_account_info();
+        match challenger_won {
+            Some(challenger_won) => {
+                let (winner, winner_card, loser_card) = if challenger_won {
+                    (&ctx.accounts.challenger, challenger_card, opponent_card)
+                } else {
+                    (&ctx.accounts.opponent, opponent_card, challenger_card)
+                };
+                let pot = stake.checked_mul(2).ok_or(PredictionError::MathOverflow)?;
+                duel_info.sub_lamports(pot)?;
+                winner.add_lamports(pot)?;
+
+                emit!(DuelSettled {
+                    duel: duel_info.key(),
+                    market: market.key(),
+                    outcome: market.outcome,
+                    winner: Some(winner.key()),
+                    payout: pot,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+
+                // Even odds: the pot is twice the winning stake
+                let experience = (stake / XP_STAKE_UNIT).saturating_mul(2);
+                winner_card.wins = winner_card.wins.checked_add(1).unwrap();
+                winner_card.experience = winner_card.experience.saturating_add(experience);
+                loser_card.losses = loser_card.losses.checked_add(1).unwrap();
+
+                emit!(CardExperienceGained {
+                    card: winner_card.key(),
+                    market: market.key(),
+                    experience,
+                    total_experience: winner_card.experience,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+
+                release_battle_slot(winner_card)?;
+                release_battle_slot(loser_card)?;
+                emit_card_stats_updated(winner_card)?;
+                emit_card_stats_updated(loser_card)?;
+            }
+            None => {
+                // The challenger's stake returns with the account rent on close
+                duel_info.sub_lamports(stake)?;
+                ctx.accounts.opponent.add_lamports(stake)?;
+
+                release_battle_slot(challenger_card)?;
+                release_battle_slot(opponent_card)?;
+                emit_card_stats_updated(challenger_card)?;
+                emit_card_stats_updated(opponent_card)?;
+
+                emit!(DuelSettled {
+                    duel: duel_info.key(),
+                    market: market.key(),
+                    outcome: market.outcome,
+                    winner: None,
+                    payout: stake,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Escrows a card so other players can rent it for `duration` seconds
+    // at a flat `rent_fee` paid to the lender
+    pub fn list_card_for_rent(
+        ctx: Context<ListCardForRent>,
+        rent_fee: u64,
+        duration: i64,
+    ) -> Result<()> {
+        require!(duration > 0, PredictionError::InvalidRentalTerms);
+        // A renter would inherit the lender's open battle slots and cooldown
+        require!(ctx.accounts.card.active_battles == 0, PredictionError::CardInBattle);
+
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.lender_token_account.to_account_info(),
+                to: ctx.accounts.escrow_token_account.to_account_info(),
+                authority: ctx.accounts.lender.to_account_info(),
+            },
+        );
+        token::transfer(cpi_ctx, 1)?;
+
+        ctx.accounts.card.owner = ctx.accounts.lender.key();
+
+        let listing = &mut ctx.accounts.card_listing;
+        listing.card = ctx.accounts.card.key();
+        listing.mint = ctx.accounts.card.mint;
+        listing.lender = ctx.accounts.lender.key();
+        listing.rent_fee = rent_fee;
+        listing.duration = duration;
+        listing.renter = None;
+        listing.rented_until = 0;
+        listing.bump = ctx.bumps.card_listing;
+
+        emit!(CardListed {
+            card: listing.card,
+            mint: listing.mint,
+            lender: listing.lender,
+            rent_fee,
+            duration,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn rent_card(ctx: Context<RentCard>) -> Result<()> {
+        let listing = &ctx.accounts.card_listing;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            listing.renter.is_none() || now >= listing.rented_until,
+            PredictionError::CardRented
+        );
+        require!(
+            ctx.accounts.renter.key() != listing.lender,
+            PredictionError::Unauthorized
+        );
+
+        if listing.rent_fee > 0 {
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.renter.to_account_info(),
+                    to: ctx.accounts.lender.to_account_info(),
+                },
+            );
+            system_program::transfer(cpi_context, listing.rent_fee)?;
+        }
+
+        let listing = &mut ctx.accounts.card_listing;
+        listing.renter = Some(ctx.accounts.renter.key());
+        listing.rented_until = now.checked_add(listing.duration).ok_or(PredictionError::MathOverflow)?;
+
+        emit!(CardRented {
+            card: listing.card,
+            mint: listing.mint,
+            lender: listing.lender,
+            renter: ctx.accounts.renter.key(),
+            rent_fee: listing.rent_fee,
+            rented_until: listing.rented_until,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Returns an escrowed card to its lender. Permissionless once a rental
+    // has expired; the lender may also delist a card nobody is renting.
+    pub fn reclaim_card(ctx: Context<ReclaimCard>) -> Result<()> {
+        let listing = &ctx.accounts.card_listing;
+        match listing.renter {
+            Some(_) => require!(
+                Clock::get()?.unix_timestamp >= listing.rented_until,
+                PredictionError::CardRented
+            ),
+            None => require!(
+                ctx.accounts.caller.key() == listing.lender,
+                PredictionError::Unauthorized
+            ),
+        }
+
+        let mint = listing.mint;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"card_listing",
+            mint.as_ref(),
+            &[listing.bump],
+        ]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: ctx.accounts.lender_token_account.to_account_info(),
+                authority: ctx.accounts.card_listing.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx, 1)?;
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.escrow_token_account.to_account_info(),
+                destination: ctx.accounts.lender.to_account_info(),
+                authority: ctx.accounts.card_listing.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::close_account(cpi_ctx)?;
+
+        let listing = &ctx.accounts.card_listing;
+        emit!(CardReclaimed {
+            card: listing.card,
+            mint: listing.mint,
+            lender: listing.lender,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_lmsr(
+        ctx: Context<InitializeLmsr>,
+        liquidity_b: u64,
+    ) -> Result<()> {
+        require!(liquidity_b > 0, PredictionError::InvalidLiquidity);
+
+        let market = &ctx.accounts.market;
+        require!(!market.resolved, PredictionError::MarketAlreadyResolved);
+        require!(Clock::get()?.unix_timestamp < market.end_time, PredictionError::MarketEnded);
+        require!(
+            ctx.accounts.creator.key() == market.creator,
+            PredictionError::Unauthorized
+        );
+
+        // Creator funds the maker's worst-case loss of b * ln(2) up front
+        let subsidy = lmsr::subsidy(liquidity_b).ok_or(PredictionError::MathOverflow)?;
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.creator.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_context, subsidy)?;
+
+        let lmsr_state = &mut ctx.accounts.lmsr_state;
+        lmsr_state.market = market.key();
+        lmsr_state.creator = ctx.accounts.creator.key();
+        lmsr_state.liquidity_b = liquidity_b;
+        lmsr_state.yes_shares = 0;
+        lmsr_state.no_shares = 0;
+        lmsr_state.subsidy = subsidy;
+        lmsr_state.collected = 0;
+        lmsr_state.total_cost_basis = 0;
+        lmsr_state.settled = false;
+        lmsr_state.bump = ctx.bumps.lmsr_state;
+
+        Ok(())
+    }
+
+    pub fn quote_lmsr(
+        ctx: Context<QuoteLmsr>,
+        outcome: bool,
+        shares: u64,
+    ) -> Result<LmsrQuote> {
+        let lmsr_state = &ctx.accounts.lmsr_state;
+
+        let (yes_price, no_price) = lmsr::prices(
+            lmsr_state.yes_shares,
+            lmsr_state.no_shares,
+            lmsr_state.liquidity_b,
+        ).ok_or(PredictionError::MathOverflow)?;
+
+        let cost = lmsr::buy_cost(
+            lmsr_state.yes_shares,
+            lmsr_state.no_shares,
+            lmsr_state.liquidity_b,
+            outcome,
+            shares,
+        ).ok_or(PredictionError::MathOverflow)?;
+
+        Ok(LmsrQuote {
+            yes_price: yes_price as u64,
+            no_price: no_price as u64,
+            cost,
+        })
+    }
+
+    pub fn buy_lmsr_shares(
+        ctx: Context<TradeLmsr>,
+        outcome: bool,
+        shares: u64,
+        max_cost: u64,
+    ) -> Result<()> {
+        require!(shares > 0, PredictionError::InvalidAmount);
+
+        let market = &ctx.accounts.market;
+        require!(!market.resolved, PredictionError::MarketAlreadyResolved);
+        require!(Clock::get()?.unix_timestamp < market.end_time, PredictionError::MarketEnded);
+
+        let lmsr_state = &mut ctx.accounts.lmsr_state;
+        let cost = lmsr::buy_cost(
+            lmsr_state.yes_shares,
+            lmsr_state.no_shares,
+            lmsr_state.liquidity_b,
+            outcome,
+            shares,
+        ).ok_or(PredictionError::MathOverflow)?;
+        require!(cost <= max_cost, PredictionError::SlippageExceeded);
+
+        // Transfer SOL from trader to market vault
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.trader.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_context, cost)?;
+
+        let position = &mut ctx.accounts.position;
+        position.market = market.key();
+        position.owner = ctx.accounts.trader.key();
+        position.bump = ctx.bumps.position;
+
+        if outcome {
+            lmsr_state.yes_shares = lmsr_state.yes_shares.checked_add(shares).unwrap();
+            position.yes_shares = position.yes_shares.checked_add(shares).unwrap();
+        } else {
+            lmsr_state.no_shares = lmsr_state.no_shares.checked_add(shares).unwrap();
+            position.no_shares = position.no_shares.checked_add(shares).unwrap();
+        }
+        lmsr_state.collected = lmsr_state.collected.checked_add(cost).unwrap();
+        position.cost_basis = position.cost_basis.checked_add(cost).unwrap();
+        lmsr_state.total_cost_basis = lmsr_state.total_cost_basis.checked_add(cost).unwrap();
+
+        // Update platform volume
+        let platform = &mut ctx.accounts.platform;
+        platform.total_volume = platform.total_volume.checked_add(cost).unwrap();
+
+        emit_lmsr_traded(lmsr_state, ctx.accounts.trader.key(), outcome, true, shares, cost)
+    }
+
+    pub fn sell_lmsr_shares(
+        ctx: Context<TradeLmsr>,
+        outcome: bool,
+        shares: u64,
+        min_proceeds: u64,
+    ) -> Result<()> {
+        require!(shares > 0, PredictionError::InvalidAmount);
+
+        let market = &ctx.accounts.market;
+        require!(!market.resolved, PredictionError::MarketAlreadyResolved);
+        require!(Clock::get()?.unix_timestamp < market.end_time, PredictionError::MarketEnded);
+
+        let position = &mut ctx.accounts.position;
+        require!(position.owner == ctx.accounts.trader.key(), PredictionError::Unauthorized);
+        let held = if outcome { position.yes_shares } else { position.no_shares };
+        require!(held >= shares, PredictionError::InsufficientShares);
+
+        let lmsr_state = &mut ctx.accounts.lmsr_state;
+        let proceeds = lmsr::sell_proceeds(
+            lmsr_state.yes_shares,
+            lmsr_state.no_shares,
+            lmsr_state.liquidity_b,
+            outcome,
+            shares,
+        ).ok_or(PredictionError::MathOverflow)?;
+        require!(proceeds >= min_proceeds, PredictionError::SlippageExceeded);
+
+        if outcome {
+            lmsr_state.yes_shares = lmsr_state.yes_shares.checked_sub(shares).unwrap();
+            position.yes_shares = position.yes_shares.checked_sub(shares).unwrap();
+        } else {
+            lmsr_state.no_shares = lmsr_state.no_shares.checked_sub(shares).unwrap();
+            position.no_shares = position.no_shares.checked_sub(shares).unwrap();
+        }
+        lmsr_state.collected = lmsr_state.collected.checked_sub(proceeds).unwrap();
+        // A trader who sold at a profit has nothing left at risk
+        let basis_released = position.cost_basis.min(proceeds);
+        position.cost_basis -= basis_released;
+        lmsr_state.total_cost_basis = lmsr_state.total_cost_basis.checked_sub(basis_released).unwrap();
+
+        // Transfer proceeds from vault to trader
+        let market_key = market.key();
+        let bump = ctx.bumps.vault;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            market_key.as_ref(),
+            &[bump],
+        ]];
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.trader.key(),
+            proceeds,
+        );
+
+        anchor_lang::solana_program::program::invoke_signed(
+            &transfer_ix,
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.trader.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        emit_lmsr_traded(
+            &ctx.accounts.lmsr_state,
+            ctx.accounts.trader.key(),
+            outcome,
+            false,
+            shares,
+            proceeds,
+        )
+    }
+
+    pub fn claim_lmsr_winnings(ctx: Context<ClaimLmsrWinnings>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+
+        require!(market.resolved, PredictionError::MarketNotResolved);
+        require!(!position.claimed, PredictionError::AlreadyClaimed);
+        require!(position.owner == ctx.accounts.trader.key(), PredictionError::Unauthorized);
+
+        // Each winning share redeems for one lamport. A voided market refunds
+        // what the trader paid in net of sales, scaled down pro rata only if
+        // the vault cannot cover every trader's cost basis.
+        let lmsr_state = &ctx.accounts.lmsr_state;
+        let winnings = match market.outcome {
+            Some(true) => position.yes_shares,
+            Some(false) => position.no_shares,
+            None => (position.cost_basis as u128)
+                .checked_mul(lmsr_state.void_liability() as u128).unwrap()
+                .checked_div(lmsr_state.total_cost_basis.max(1) as u128).unwrap() as u64,
+        };
+        require!(winnings > 0, PredictionError::LosingBet);
+
+        let market_key = market.key();
+        let bump = ctx.bumps.vault;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            market_key.as_ref(),
+            &[bump],
+        ]];
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.trader.key(),
+            winnings,
+        );
+
+        anchor_lang::solana_program::program::invoke_signed(
+            &transfer_ix,
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.trader.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        position.claimed = true;
+
+        emit!(SharesRedeemed {
+            market: market.key(),
+            owner: position.owner,
+            outcome: market.outcome,
+            yes_shares: position.yes_shares,
+            no_shares: position.no_shares,
+            payout: winnings,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_lmsr(ctx: Context<CloseLmsr>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        let lmsr_state = &mut ctx.accounts.lmsr_state;
+
+        require!(market.resolved, PredictionError::MarketNotResolved);
+        require!(!lmsr_state.settled, PredictionError::AlreadyClaimed);
+        require!(
+            ctx.accounts.creator.key() == lmsr_state.creator,
+            PredictionError::Unauthorized
+        );
+
+        // Whatever is left after covering every winning share, or every
+        // refund on a voided market, goes back to the creator
+        let liability = match market.outcome {
+            Some(true) => lmsr_state.yes_shares,
+            Some(false) => lmsr_state.no_shares,
+            None => lmsr_state.void_liability(),
+        };
+        let remainder = lmsr_state.subsidy
+            .checked_add(lmsr_state.collected).unwrap()
+            .checked_sub(liability)
+            .ok_or(PredictionError::MathOverflow)?;
+
+        let market_key = market.key();
+        let bump = ctx.bumps.vault;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            market_key.as_ref(),
+            &[bump],
+        ]];
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.creator.key(),
+            remainder,
+        );
+
+        anchor_lang::solana_program::program::invoke_signed(
+            &transfer_ix,
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.creator.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        lmsr_state.settled = true;
+
+        Ok(())
+    }
+
+    pub fn initialize_order_book(ctx: Context<InitializeOrderBook>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(!market.resolved, PredictionError::MarketAlreadyResolved);
+
+        let order_book = &mut ctx.accounts.order_book.load_init()?;
+        order_book.market = market.key();
+        order_book.next_order_id = 1;
+
+        let event_queue = &mut ctx.accounts.event_queue.load_init()?;
+        event_queue.market = market.key();
+
+        Ok(())
+    }
+
+    pub fn place_order(
+        ctx: Context<PlaceOrder>,
+        side: OrderSide,
+        price: u64,
+        quantity: u64,
+    ) -> Result<()> {
+        require!(quantity >= MIN_BOOK_ORDER_QUANTITY, PredictionError::OrderTooSmall);
+        require!(
+            price > 0 && price < BOOK_SHARE_LAMPORTS,
+            PredictionError::InvalidOrderPrice
+        );
+
+        let market = &ctx.accounts.market;
+        require!(!market.resolved, PredictionError::MarketAlreadyResolved);
+        require!(Clock::get()?.unix_timestamp < market.end_time, PredictionError::MarketEnded);
+
+        // Bids lock the YES price, asks lock the complementary NO price, so
+        // every matched share is fully collateralised in the vault
+        let escrow_price = match side {
+            OrderSide::Bid => price,
+            OrderSide::Ask => BOOK_SHARE_LAMPORTS - price,
+        };
+        let escrow = quantity
+            .checked_mul(escrow_price)
+            .ok_or(PredictionError::MathOverflow)?;
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.owner.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_context, escrow)?;
+
+        let order_book = &mut ctx.accounts.order_book.load_mut()?;
+        let open_orders = order_book
+            .orders(side)
+            .iter()
+            .filter(|order| order.owner == ctx.accounts.owner.key())
+            .count();
+        require!(open_orders < MAX_ORDERS_PER_OWNER, PredictionError::TooManyOpenOrders);
+
+        let order = BookOrder {
+            owner: ctx.accounts.owner.key(),
+            order_id: order_book.next_order_id,
+            price,
+            quantity,
+            escrow,
+        };
+        order_book.insert(side, order)?;
+        order_book.next_order_id = order_book.next_order_id.checked_add(1).unwrap();
+
+        let position = &mut ctx.accounts.position;
+        position.market = market.key();
+        position.owner = ctx.accounts.owner.key();
+        position.bump = ctx.bumps.position;
+
+        emit!(OrderPlaced {
+            market: market.key(),
+            owner: order.owner,
+            order_id: order.order_id,
+            side,
+            price,
+            quantity,
+            escrow,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_order(
+        ctx: Context<CancelOrder>,
+        side: OrderSide,
+        order_id: u64,
+    ) -> Result<()> {
+        let order_book = &mut ctx.accounts.order_book.load_mut()?;
+        let index = order_book.find(side, order_id)
+            .ok_or(PredictionError::OrderNotFound)?;
+        let order = order_book.orders(side)[index];
+        require!(order.owner == ctx.accounts.owner.key(), PredictionError::Unauthorized);
+        order_book.remove(side, index);
+
+        // Refund whatever escrow the order has not spent on fills
+        let market_key = ctx.accounts.market.key();
+        let bump = ctx.bumps.vault;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            market_key.as_ref(),
+            &[bump],
+        ]];
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.owner.key(),
+            order.escrow,
+        );
+
+        anchor_lang::solana_program::program::invoke_signed(
+            &transfer_ix,
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        emit!(OrderCancelled {
+            market: market_key,
+            owner: order.owner,
+            order_id,
+            side,
+            remaining_quantity: order.quantity,
+            refund: order.escrow,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Permissionless crank: cross the book and queue fills for consume_events
+    pub fn match_orders(ctx: Context<MatchOrders>, limit: u8) -> Result<()> {
+        let market = &ctx.accounts.market;
+        require!(!market.resolved, PredictionError::MarketAlreadyResolved);
+
+        let order_book = &mut ctx.accounts.order_book.load_mut()?;
+        let event_queue = &mut ctx.accounts.event_queue.load_mut()?;
+        let mut matched_volume: u64 = 0;
+        let now = Clock::get()?.unix_timestamp;
+
+        for _ in 0..limit {
+            if order_book.bid_count == 0 || order_book.ask_count == 0 || event_queue.is_full() {
+                break;
+            }
+            let bid = order_book.bids[0];
+            let ask = order_book.asks[0];
+            if bid.price < ask.price {
+                break;
+            }
+
+            // The resting (older) order sets the trade price
+            let price = if bid.order_id < ask.order_id { bid.price } else { ask.price };
+            let quantity = bid.quantity.min(ask.quantity);
+            let bid_cost = quantity * price;
+            let ask_cost = quantity * (BOOK_SHARE_LAMPORTS - price);
+
+            let bid_left = bid.quantity - quantity;
+            let ask_left = ask.quantity - quantity;
+            let bid_escrow = bid.escrow.checked_sub(bid_cost).unwrap();
+            let ask_escrow = ask.escrow.checked_sub(ask_cost).unwrap();
+
+            // Fully filled orders release any escrow left over from price improvement
+            let mut event = FillEvent {
+                bidder: bid.owner,
+                asker: ask.owner,
+                bid_order_id: bid.order_id,
+                ask_order_id: ask.order_id,
+                price,
+                quantity,
+                bid_refund: 0,
+                ask_refund: 0,
+            };
+
+            if bid_left == 0 {
+                event.bid_refund = bid_escrow;
+                order_book.remove(OrderSide::Bid, 0);
+            } else {
+                order_book.bids[0].quantity = bid_left;
+                order_book.bids[0].escrow = bid_escrow;
+            }
+            if ask_left == 0 {
+                event.ask_refund = ask_escrow;
+                order_book.remove(OrderSide::Ask, 0);
+            } else {
+                order_book.asks[0].quantity = ask_left;
+                order_book.asks[0].escrow = ask_escrow;
+            }
+
+            event_queue.push(event)?;
+            emit!(OrderFilled {
+                market: market.key(),
+                bidder: bid.owner,
+                asker: ask.owner,
+                bid_order_id: bid.order_id,
+                ask_order_id: ask.order_id,
+                taker_side: if bid.order_id < ask.order_id { OrderSide::Ask } else { OrderSide::Bid },
+                price,
+                quantity,
+                timestamp: now,
+            });
+            matched_volume = matched_volume
+                .checked_add(quantity.checked_mul(BOOK_SHARE_LAMPORTS).unwrap())
+                .unwrap();
+        }
+
+        // Update platform volume
+        let platform = &mut ctx.accounts.platform;
+        platform.total_volume = platform.total_volume.checked_add(matched_volume).unwrap();
+
+        Ok(())
+    }
+
+    // Permissionless crank: apply queued fills to the BookPosition accounts
+    // passed in remaining_accounts
+    pub fn consume_events<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ConsumeEvents<'info>>,
+        limit: u8,
+    ) -> Result<()> {
+        let market_key = ctx.accounts.market.key();
+        let event_queue = &mut ctx.accounts.event_queue.load_mut()?;
+
+        // A repeated account would be written back stale over the fills
+        // applied to its first copy, so every position may appear once
+        let mut positions: Vec<Account<BookPosition>> = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts.iter() {
+            require!(
+                !positions.iter().any(|p| p.key() == info.key()),
+                PredictionError::DuplicatePosition
+            );
+            let position = Account::<BookPosition>::try_from(info)?;
+            require!(position.market == market_key, PredictionError::InvalidPosition);
+            positions.push(position);
+        }
+
+        for _ in 0..limit {
+            let event = match event_queue.peek() {
+                Some(event) => event,
+                None => break,
+            };
+
+            let bidder = positions.iter().position(|p| p.owner == event.bidder);
+            let asker = positions.iter().position(|p| p.owner == event.asker);
+            let (bidder, asker) = match (bidder, asker) {
+                (Some(bidder), Some(asker)) => (bidder, asker),
+                _ => break,
+            };
+
+            let bid_cost = event.quantity.checked_mul(event.price).unwrap();
+            let ask_cost = event.quantity.checked_mul(BOOK_SHARE_LAMPORTS - event.price).unwrap();
+
+            let bidder = &mut positions[bidder];
+            bidder.yes_shares = bidder.yes_shares.checked_add(event.quantity).unwrap();
+            bidder.free_lamports = bidder.free_lamports.checked_add(event.bid_refund).unwrap();
+            bidder.cost_basis = bidder.cost_basis.checked_add(bid_cost).unwrap();
+
+            let asker = &mut positions[asker];
+            asker.no_shares = asker.no_shares.checked_add(event.quantity).unwrap();
+            asker.free_lamports = asker.free_lamports.checked_add(event.ask_refund).unwrap();
+            asker.cost_basis = asker.cost_basis.checked_add(ask_cost).unwrap();
+
+            event_queue.pop();
+        }
+
+        for position in positions.iter() {
+            position.exit(&crate::ID)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn settle_book_funds(ctx: Context<SettleBookFunds>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        require!(position.owner == ctx.accounts.owner.key(), PredictionError::Unauthorized);
+
+        let amount = position.free_lamports;
+        require!(amount > 0, PredictionError::InvalidAmount);
+        position.free_lamports = 0;
+
+        let market_key = ctx.accounts.market.key();
+        let bump = ctx.bumps.vault;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            market_key.as_ref(),
+            &[bump],
+        ]];
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.owner.key(),
+            amount,
+        );
+
+        anchor_lang::solana_program::program::invoke_signed(
+            &transfer_ix,
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn claim_book_winnings(ctx: Context<ClaimBookWinnings>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+
+        require!(market.resolved, PredictionError::MarketNotResolved);
+        require!(!position.claimed, PredictionError::AlreadyClaimed);
+        require!(position.owner == ctx.accounts.owner.key(), PredictionError::Unauthorized);
+        // Shares still queued would be lost once the position is marked claimed
+        require!(
+            !ctx.accounts.event_queue.load()?.has_fills_for(position.owner),
+            PredictionError::PendingFills
+        );
+
+        // Every share is backed by a complete set paid for by both sides, so
+        // a voided market refunds each side exactly what it paid at fill prices
+        let winnings = match market.outcome {
+            Some(true) => position.yes_shares.checked_mul(BOOK_SHARE_LAMPORTS).unwrap(),
+            Some(false) => position.no_shares.checked_mul(BOOK_SHARE_LAMPORTS).unwrap(),
+            None => position.cost_basis,
+        };
+        require!(winnings > 0, PredictionError::LosingBet);
+
+        let market_key = market.key();
+        let bump = ctx.bumps.vault;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            market_key.as_ref(),
+            &[bump],
+        ]];
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.owner.key(),
+            winnings,
+        );
+
+        anchor_lang::solana_program::program::invoke_signed(
+            &transfer_ix,
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        position.claimed = true;
+
+        emit!(SharesRedeemed {
+            market: market.key(),
+            owner: position.owner,
+            outcome: market.outcome,
+            yes_shares: position.yes_shares,
+            no_shares: position.no_shares,
+            payout: winnings,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// Records a resolved bet's result, exactly once per bet and whoever triggers
+// it: realised PnL on the bettor's profile and, for battle bets, the card's
+// win or loss. PnL is measured on what the bettor staked through place_bet and
+// battle, so position tokens bought or sold elsewhere do not move it.
+fn settle_bet_result(
+    bet: &mut Bet,
+    card: Option<&mut Account<Card>>,
+    profile: &mut UserProfile,
+    market: &Account<Market>,
+) -> Result<()> {
+    if bet.settled {
+        return Ok(());
+    }
+    if let Some(card_mint) = bet.card_mint {
+        let card = card.ok_or(PredictionError::CardAccountRequired)?;
+        require!(card.mint == card_mint, PredictionError::CardMismatch);
+        record_card_result(bet, card, market)?;
+    }
+    if let Some(outcome) = market.outcome {
+        let (winning_stake, losing_stake) = bet.stakes(outcome);
+        let payout = market.winning_payout(winning_stake, outcome);
+        profile.record_settlement(payout, winning_stake.checked_add(losing_stake).unwrap(), market.season_id);
+    }
+    bet.settled = true;
+    Ok(())
+}
+
+fn record_card_result(bet: &Bet, card: &mut Account<Card>, market: &Account<Market>) -> Result<()> {
+    // A voided market frees the card without a win or a loss
+    let Some(outcome) = market.outcome else {
+        release_battle_slot(card)?;
+        return emit_card_stats_updated(card);
+    };
+
+    // Only stake placed through battle counts, and a hedged battle only wins
+    // if the winning side carried more of it
+    let (winning_stake, losing_stake) = bet.card_stakes(outcome);
+    if winning_stake > losing_stake {
+        card.wins = card.wins.checked_add(1).unwrap();
+
+        // XP scales with the net battle stake and with how long the odds were
+        let winning_pool = if outcome {
+            market.total_yes_amount
+        } else {
+            market.total_no_amount
+        };
+        let net_stake = winning_stake - losing_stake;
+        let experience = ((net_stake / XP_STAKE_UNIT) as u128)
+            .checked_mul(market.total_pool() as u128).unwrap()
+            .checked_div(winning_pool.max(1) as u128).unwrap();
+        let experience = u64::try_from(experience).unwrap_or(u64::MAX);
+        card.experience = card.experience.saturating_add(experience);
+
+        emit!(CardExperienceGained {
+            card: card.key(),
+            market: market.key(),
+            experience,
+            total_experience: card.experience,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    } else {
+        card.losses = card.losses.checked_add(1).unwrap();
+    }
+    release_battle_slot(card)?;
+    emit_card_stats_updated(card)
+}
+
+// A new battle or duel counts against the card's per-rarity concurrency
+// limit and must wait out the cooldown from its last settlement
+fn reserve_battle_slot(card: &mut Card, card_config: &CardConfig) -> Result<()> {
+    require!(
+        Clock::get()?.unix_timestamp >= card.last_settled_at.saturating_add(card_config.battle_cooldown),
+        PredictionError::CardOnCooldown
+    );
+    let tier = card_config.tiers
+        .get(card.rarity as usize)
+        .ok_or(PredictionError::InvalidRarity)?;
+    require!(
+        card.active_battles < tier.max_active_battles,
+        PredictionError::TooManyActiveBattles
+    );
+    card.active_battles = card.active_battles.checked_add(1).unwrap();
+    Ok(())
+}
+
+// Free the battle slot and start the cooldown
+fn release_battle_slot(card: &mut Card) -> Result<()> {
+    card.active_battles = card.active_battles.saturating_sub(1);
+    card.last_settled_at = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+// Moves a manual market's bond from the creator's profile into the vault,
+// where it is paid out with the pool
+fn slash_creator_bond<'info>(
+    market: &mut Account<'info, Market>,
+    creator_profile: &mut Account<'info, CreatorProfile>,
+    vault: &AccountInfo<'info>,
+) -> Result<()> {
+    let bond = market.creator_bond;
+    if bond == 0 {
+        return Ok(());
+    }
+    creator_profile.locked_stake = creator_profile.locked_stake.checked_sub(bond).unwrap();
+    creator_profile.sub_lamports(bond)?;
+    vault.add_lamports(bond)?;
+
+    market.forfeited_amount = market.forfeited_amount.checked_add(bond).unwrap();
+    market.creator_bond = 0;
+    Ok(())
+}
+
+// Moves an expired market's creator stake from the creator's profile into
+// the vault, where it is refunded with the pool
+fn forfeit_creator_stake<'info>(
+    market: &mut Account<'info, Market>,
+    creator_profile: &mut Account<'info, CreatorProfile>,
+    vault: &AccountInfo<'info>,
+) -> Result<()> {
+    let stake = market.creator_stake;
+    market.stake_released = true;
+    if stake == 0 {
+        return Ok(());
+    }
+    creator_profile.locked_stake = creator_profile.locked_stake.checked_sub(stake).unwrap();
+    creator_profile.sub_lamports(stake)?;
+    vault.add_lamports(stake)?;
+
+    market.forfeited_amount = market.forfeited_amount.checked_add(stake).unwrap();
+    Ok(())
+}
+
+fn emit_market_resolved(market: &Account<Market>) -> Result<()> {
+    emit!(MarketResolved {
+        market: market.key(),
+        oracle_source: market.oracle_source,
+        outcome: market.outcome,
+        total_yes_amount: market.total_yes_amount,
+        total_no_amount: market.total_no_amount,
+        forfeited_amount: market.forfeited_amount,
+        strike_price: market.strike_price,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+fn emit_card_minted(card: &Account<Card>, fused: bool) -> Result<()> {
+    emit!(CardMinted {
+        card: card.key(),
+        mint: card.mint,
+        owner: card.owner,
+        rarity: card.rarity,
+        power: card.power,
+        multiplier: card.multiplier,
+        season_id: card.season_id,
+        fused,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+fn emit_card_stats_updated(card: &Account<Card>) -> Result<()> {
+    emit!(CardStatsUpdated {
+        card: card.key(),
+        wins: card.wins,
+        losses: card.losses,
+        experience: card.experience,
+        level: card.level,
+        active_battles: card.active_battles,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+fn emit_lmsr_traded(
+    lmsr_state: &LmsrState,
+    trader: Pubkey,
+    outcome: bool,
+    buy: bool,
+    shares: u64,
+    amount: u64,
+) -> Result<()> {
+    let (yes_price, no_price) = lmsr::prices(
+        lmsr_state.yes_shares,
+        lmsr_state.no_shares,
+        lmsr_state.liquidity_b,
+    ).ok_or(PredictionError::MathOverflow)?;
+
+    emit!(LmsrTraded {
+        market: lmsr_state.market,
+        trader,
+        outcome,
+        buy,
+        shares,
+        amount,
+        yes_price: yes_price as u64,
+        no_price: no_price as u64,
+        yes_shares: lmsr_state.yes_shares,
+        no_shares: lmsr_state.no_shares,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+// Creates Metaplex metadata and a zero-supply master edition. The master
+// edition takes over the mint and freeze authorities, so the card's supply
+// is permanently fixed at the single token already minted. The card_authority
+// PDA is the update authority on both, so it signs each CPI.
+fn create_card_metadata<'info>(
+    card_config: &CardConfig,
+    rarity: u8,
+    card_authority_bump: u8,
+    token_metadata_program: AccountInfo<'info>,
+    metadata_accounts: CreateMetadataAccountsV3<'info>,
+    edition_accounts: CreateMasterEditionV3<'info>,
+) -> Result<()> {
+    let rarity_name = RARITY_NAMES
+        .get(rarity as usize)
+        .ok_or(PredictionError::InvalidRarity)?;
+
+    let data = DataV2 {
+        name: format!("{} Prediction Card", rarity_name),
+        symbol: CARD_SYMBOL.to_string(),
+        uri: card_config.uri_template.replace("{rarity}", &rarity_name.to_lowercase()),
+        seller_fee_basis_points: 0,
+        creators: None,
+        collection: None,
+        uses: None,
+    };
+    let signer_seeds: &[&[&[u8]]] = &[&[b"card_authority", &[card_authority_bump]]];
+    create_metadata_accounts_v3(
+        CpiContext::new_with_signer(token_metadata_program.clone(), metadata_accounts, signer_seeds),
+        data,
+        true,
+        false,
+        None,
+    )?;
+
+    create_master_edition_v3(
+        CpiContext::new_with_signer(token_metadata_program, edition_accounts, signer_seeds),
+        Some(0),
+    )?;
+
+    Ok(())
+}
+
+fn validate_rarity_tiers(tiers: &[RarityTier]) -> Result<()> {
+    require!(
+        !tiers.is_empty() && tiers.len() <= MAX_RARITY_TIERS,
+        PredictionError::InvalidRarity
+    );
+    for tier in tiers {
+        require!(
+            tier.min_power <= tier.max_power && tier.min_multiplier <= tier.max_multiplier,
+            PredictionError::CardStatsOutOfRange
+        );
+        require!(tier.max_active_battles > 0, PredictionError::InvalidRarity);
+    }
+    require!(
+        tiers.iter().any(|tier| tier.weight > 0),
+        PredictionError::InvalidRarity
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializePlatform<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Platform::INIT_SPACE,
+        seeds = [b"platform"],
+        bump
+    )]
+    pub platform: Account<'info, Platform>,
+    /// CHECK: Treasury can be any account
+    pub treasury: AccountInfo<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePlatform<'info> {
+    #[account(mut)]
+    pub platform: Account<'info, Platform>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundBonusPool<'info> {
+    #[account(mut)]
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        seeds = [b"bonus_pool"],
+        bump
+    )]
+    /// CHECK: Bonus pool PDA funding card multipliers
+    pub bonus_pool: AccountInfo<'info>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+    // Keyed by platform-wide creation order; see Market::pda
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market", platform.total_markets.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA, mint authority for position tokens
+    pub vault: AccountInfo<'info>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump,
+        mint::decimals = POSITION_MINT_DECIMALS,
+        mint::authority = vault,
+    )]
+    pub yes_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"no_mint", market.key().as_ref()],
+        bump,
+        mint::decimals = POSITION_MINT_DECIMALS,
+        mint::authority = vault,
+    )]
+    pub no_mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + CreatorProfile::INIT_SPACE,
+        seeds = [b"creator_profile", authority.key().as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, prediction: bool)]
+pub struct PlaceBet<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub platform: Account<'info, Platform>,
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = 8 + Bet::INIT_SPACE,
+        seeds = [b"bet", market.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"user_profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    /// Referrer to attach on the wallet's first bet
+    pub referrer_profile: Option<Account<'info, UserProfile>>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding bets
+    pub vault: AccountInfo<'info>,
+    #[account(
+        mut,
+        address = if prediction { market.yes_mint } else { market.no_mint }
+    )]
+    pub position_mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        associated_token::mint = position_mint,
+        associated_token::authority = bettor,
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, prediction: bool)]
+pub struct WithdrawBet<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        seeds = [b"bet", market.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    #[account(
+        seeds = [b"user_profile", bettor.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding bets
+    pub vault: AccountInfo<'info>,
+    #[account(
+        mut,
+        address = if prediction { market.yes_mint } else { market.no_mint }
+    )]
+    pub position_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = position_mint,
+        token::authority = bettor,
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMarket<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", market.creator.as_ref()],
+        bump = creator_profile.bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA, receives a slashed bond
+    pub vault: AccountInfo<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireMarket<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", market.creator.as_ref()],
+        bump = creator_profile.bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA, receives the slashed bond and forfeited stake
+    pub vault: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding bets
+    pub vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = position_mint,
+        token::authority = bettor,
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMarketWithOracle<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    /// CHECK: Pyth price feed account, validated in instruction
+    pub price_feed: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMarketSports<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMarketWeather<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMarketSocial<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding bets
+    pub vault: AccountInfo<'info>,
+    /// Only needed to apply a card bonus from a battle bet
+    #[account(
+        mut,
+        seeds = [b"bet", market.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub bet: Option<Account<'info, Bet>>,
+    /// Required when `bet` is a battle bet, to record the card's result
+    #[account(mut)]
+    pub card: Option<Account<'info, Card>>,
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"user_profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    /// Required when the claimant was referred, to credit the referrer
+    #[account(mut)]
+    pub referrer_profile: Option<Account<'info, UserProfile>>,
+    #[account(
+        mut,
+        seeds = [b"bonus_pool"],
+        bump
+    )]
+    /// CHECK: Bonus pool PDA funding card multipliers
+    pub bonus_pool: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = position_mint,
+        token::authority = bettor,
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleBet<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"bet", market.key().as_ref(), bet.bettor.as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    /// Required when `bet` is a battle bet, to record the card's result
+    #[account(mut)]
+    pub card: Option<Account<'info, Card>>,
+    #[account(
+        mut,
+        seeds = [b"user_profile", bet.bettor.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBet<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        close = bettor,
+        seeds = [b"bet", market.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    /// Required when `bet` is a battle bet, to record the card's result
+    #[account(mut)]
+    pub card: Option<Account<'info, Card>>,
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"user_profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CollectPlatformFee<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding bets
+    pub vault: AccountInfo<'info>,
+    #[account(mut)]
+    /// CHECK: Treasury account from platform
+    pub treasury: AccountInfo<'info>,
+    /// Required while a season is active and the leaderboard share is non-zero
+    #[account(mut)]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelCardMintRequest<'info> {
+    #[account(
+        seeds = [b"season", request.season_id.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = randomness,
+    )]
+    pub request: Account<'info, CardMintRequest>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"randomness", request.key().as_ref()],
+        bump = randomness.bump
+    )]
+    pub randomness: Account<'info, Randomness>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SyncCardOwner<'info> {
+    #[account(
+        mut,
+        seeds = [b"card", card.mint.as_ref()],
+        bump = card.bump
+    )]
+    pub card: Account<'info, Card>,
+    #[account(
+        constraint = card_token_account.mint == card.mint
+    )]
+    pub card_token_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, prediction: bool)]
+pub struct Battle<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        seeds = [b"card", card.mint.as_ref()],
+        bump = card.bump
+    )]
+    pub card: Account<'info, Card>,
+    #[account(
+        seeds = [b"card_config"],
+        bump = card_config.bump
+    )]
+    pub card_config: Account<'info, CardConfig>,
+    #[account(
+        constraint = card_token_account.mint == card.mint
+    )]
+    pub card_token_account: Account<'info, TokenAccount>,
+    pub card_listing: Option<Account<'info, CardListing>>, // Required when battling with a rented card
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + Bet::INIT_SPACE,
+        seeds = [b"bet", market.key().as_ref(), player.key().as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"user_profile", player.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    /// Referrer to attach on the wallet's first bet
+    pub referrer_profile: Option<Account<'info, UserProfile>>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding bets
+    pub vault: AccountInfo<'info>,
+    #[account(
+        mut,
+        address = if prediction { market.yes_mint } else { market.no_mint }
+    )]
+    pub position_mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = player,
+        associated_token::mint = position_mint,
+        associated_token::authority = player,
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateDuel<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        seeds = [b"card_config"],
+        bump = card_config.bump
+    )]
+    pub card_config: Account<'info, CardConfig>,
+    #[account(
+        mut,
+        seeds = [b"card", card.mint.as_ref()],
+        bump = card.bump
+    )]
+    pub card: Account<'info, Card>,
+    #[account(
+        constraint = card_token_account.mint == card.mint
+    )]
+    pub card_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = challenger,
+        space = 8 + Duel::INIT_SPACE,
+        seeds = [b"duel", market.key().as_ref(), card.key().as_ref()],
+        bump
+    )]
+    pub duel: Account<'info, Duel>,
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptDuel<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        seeds = [b"card_config"],
+        bump = card_config.bump
+    )]
+    pub card_config: Account<'info, CardConfig>,
+    #[account(
+        mut,
+        has_one = market,
+        seeds = [b"duel", market.key().as_ref(), duel.challenger_card.as_ref()],
+        bump = duel.bump
+    )]
+    pub duel: Account<'info, Duel>,
+    #[account(
+        mut,
+        seeds = [b"card", card.mint.as_ref()],
+        bump = card.bump
+    )]
+    pub card: Account<'info, Card>,
+    #[account(
+        constraint = card_token_account.mint == card.mint
+    )]
+    pub card_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub opponent: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelDuel<'info> {
+    #[account(
+        mut,
+        close = challenger,
+        has_one = challenger,
+        has_one = challenger_card,
+    )]
+    pub duel: Account<'info, Duel>,
+    #[account(mut)]
+    pub challenger_card: Account<'info, Card>,
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleDuel<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        close = challenger,
+        has_one = market,
+        has_one = challenger,
+        has_one = challenger_card,
+        constraint = duel.opponent == Some(opponent.key()) @ PredictionError::DuelNotAccepted,
+        constraint = duel.opponent_card == Some(opponent_card.key()) @ PredictionError::CardMismatch,
+    )]
+    pub duel: Account<'info, Duel>,
+    #[account(mut)]
+    pub challenger_card: Account<'info, Card>,
+    #[account(mut)]
+    pub opponent_card: Account<'info, Card>,
+    #[account(mut)]
+    /// CHECK: Challenger recorded on the duel, receives the pot or the rent
+    pub challenger: AccountInfo<'info>,
+    #[account(mut)]
+    /// CHECK: Opponent recorded on the duel
+    pub opponent: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ListCardForRent<'info> {
+    #[account(
+        mut,
+        seeds = [b"card", card.mint.as_ref()],
+        bump = card.bump
+    )]
+    pub card: Account<'info, Card>,
+    #[account(address = card.mint)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = lender,
+        space = 8 + CardListing::INIT_SPACE,
+        seeds = [b"card_listing", card.mint.as_ref()],
+        bump
+    )]
+    pub card_listing: Account<'info, CardListing>,
+    #[account(
+        mut,
+        constraint = lender_token_account.mint == card.mint,
+        constraint = lender_token_account.owner == lender.key() @ PredictionError::NotCardOwner,
+    )]
+    pub lender_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = lender,
+        associated_token::mint = mint,
+        associated_token::authority = card_listing,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RentCard<'info> {
+    #[account(
+        mut,
+        has_one = lender,
+        seeds = [b"card_listing", card_listing.mint.as_ref()],
+        bump = card_listing.bump
+    )]
+    pub card_listing: Account<'info, CardListing>,
+    #[account(mut)]
+    /// CHECK: Lender recorded on the listing, receives the rent fee
+    pub lender: AccountInfo<'info>,
+    #[account(mut)]
+    pub renter: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimCard<'info> {
+    #[account(
+        mut,
+        close = lender,
+        has_one = lender,
+        seeds = [b"card_listing", card_listing.mint.as_ref()],
+        bump = card_listing.bump
+    )]
+    pub card_listing: Account<'info, CardListing>,
+    #[account(
+        mut,
+        associated_token::mint = card_listing.mint,
+        associated_token::authority = card_listing,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = card_listing.mint,
+        associated_token::authority = lender,
+    )]
+    pub lender_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    /// CHECK: Lender recorded on the listing, receives the card and rent
+    pub lender: AccountInfo<'info>,
+    pub caller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCardConfig<'info> {
+    pub platform: Account<'info, Platform>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + CardConfig::INIT_SPACE,
+        seeds = [b"card_config"],
+        bump
+    )]
+    pub card_config: Account<'info, CardConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCardConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"card_config"],
+        bump = card_config.bump
+    )]
+    pub card_config: Account<'info, CardConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(season_id: u32)]
+pub struct StartSeason<'info> {
+    #[account(mut)]
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        seeds = [b"card_config"],
+        bump = card_config.bump
+    )]
+    pub card_config: Account<'info, CardConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Season::INIT_SPACE,
+        seeds = [b"season", season_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub season: Account<'info, Season>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Leaderboard::INIT_SPACE,
+        seeds = [b"leaderboard", season_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EndSeason<'info> {
+    #[account(mut)]
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        seeds = [b"card_config"],
+        bump = card_config.bump
+    )]
+    pub card_config: Account<'info, CardConfig>,
+    #[account(
+        mut,
+        seeds = [b"season", card_config.current_season.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitLeaderboardEntry<'info> {
+    #[account(
+        seeds = [b"season", leaderboard.season_id.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+    #[account(
+        mut,
+        seeds = [b"leaderboard", leaderboard.season_id.to_le_bytes().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        seeds = [b"user_profile", user_profile.owner.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeLeaderboardPrizes<'info> {
+    pub platform: Account<'info, Platform>,
+    #[account(
+        seeds = [b"season", leaderboard.season_id.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+    #[account(
+        mut,
+        seeds = [b"leaderboard", leaderboard.season_id.to_le_bytes().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(mut)]
+    /// CHECK: Treasury account from platform, receives unallocated prizes
+    pub treasury: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseCreatorStake<'info> {
+    #[account(mut, has_one = creator)]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", creator.key().as_ref()],
+        bump = creator_profile.bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(mut)]
+    /// CHECK: Market creator, receives the released stake
+    pub creator: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FlagMarketDispute<'info> {
+    pub platform: Account<'info, Platform>,
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", market.creator.as_ref()],
+        bump = creator_profile.bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RuleMarketDispute<'info> {
     pub platform: Account<'info, Platform>,
-    /// CHECK: Treasury can be any account
-    pub treasury: AccountInfo<'info>,
     #[account(mut)]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", market.creator.as_ref()],
+        bump = creator_profile.bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(mut)]
+    /// CHECK: Treasury account from platform, receives a slashed bond
+    pub treasury: AccountInfo<'info>,
     pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"user_profile", owner.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LevelUp<'info> {
+    #[account(
+        seeds = [b"card_config"],
+        bump = card_config.bump
+    )]
+    pub card_config: Account<'info, CardConfig>,
+    #[account(
+        mut,
+        seeds = [b"card", card.mint.as_ref()],
+        bump = card.bump
+    )]
+    pub card: Account<'info, Card>,
+    #[account(
+        constraint = card_token_account.mint == card.mint,
+        constraint = card_token_account.owner == owner.key()
+    )]
+    pub card_token_account: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MintCard<'info> {
+    #[account(
+        seeds = [b"card_config"],
+        bump = card_config.bump
+    )]
+    pub card_config: Account<'info, CardConfig>,
+    #[account(
+        mut,
+        seeds = [b"season", card_config.current_season.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Card::INIT_SPACE,
+        seeds = [b"card", mint.key().as_ref()],
+        bump
+    )]
+    pub card: Account<'info, Card>,
+    #[account(
+        init,
+        payer = payer,
+        mint::decimals = 0,
+        mint::authority = payer,
+        mint::freeze_authority = payer,
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    /// CHECK: Metaplex metadata PDA, created by the token metadata program
+    pub metadata: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref(), b"edition"],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    /// CHECK: Metaplex master edition PDA, created by the token metadata program
+    pub master_edition: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"card_authority"],
+        bump
+    )]
+    /// CHECK: Program PDA that holds update authority over card metadata
+    pub card_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
     pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-#[instruction(question: String)]
-pub struct CreateMarket<'info> {
+#[instruction(seed: [u8; 32])]
+pub struct RequestCardMint<'info> {
+    #[account(
+        seeds = [b"card_config"],
+        bump = card_config.bump
+    )]
+    pub card_config: Account<'info, CardConfig>,
+    #[account(
+        seeds = [b"season", card_config.current_season.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
     #[account(
         init,
-        payer = authority,
-        space = 8 + Market::INIT_SPACE,
-        seeds = [b"market", authority.key().as_ref(), &question.as_bytes()[..question.len().min(32)]],
+        payer = owner,
+        space = 8 + CardMintRequest::INIT_SPACE,
+        seeds = [b"card_mint_request", owner.key().as_ref(), seed.as_ref()],
+        bump
+    )]
+    pub request: Account<'info, CardMintRequest>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Randomness::INIT_SPACE,
+        seeds = [b"randomness", request.key().as_ref()],
+        bump
+    )]
+    pub randomness: Account<'info, Randomness>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(seed: [u8; 32])]
+pub struct FuseCards<'info> {
+    #[account(
+        seeds = [b"card_config"],
+        bump = card_config.bump
+    )]
+    pub card_config: Account<'info, CardConfig>,
+    #[account(
+        mut,
+        seeds = [b"season", card_config.current_season.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + CardMintRequest::INIT_SPACE,
+        seeds = [b"card_mint_request", owner.key().as_ref(), seed.as_ref()],
+        bump
+    )]
+    pub request: Account<'info, CardMintRequest>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Randomness::INIT_SPACE,
+        seeds = [b"randomness", request.key().as_ref()],
+        bump
+    )]
+    pub randomness: Account<'info, Randomness>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FulfillRandomness<'info> {
+    #[account(mut)]
+    pub randomness: Account<'info, Randomness>,
+    pub oracle: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FulfillCardMint<'info> {
+    pub platform: Account<'info, Platform>,
+    #[account(
+        seeds = [b"card_config"],
+        bump = card_config.bump
+    )]
+    pub card_config: Account<'info, CardConfig>,
+    #[account(mut)]
+    /// CHECK: Treasury account from platform, receives the escrowed mint price
+    pub treasury: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"season", request.season_id.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = randomness,
+    )]
+    pub request: Account<'info, CardMintRequest>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"randomness", request.key().as_ref()],
+        bump = randomness.bump
+    )]
+    pub randomness: Account<'info, Randomness>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Card::INIT_SPACE,
+        seeds = [b"card", mint.key().as_ref()],
+        bump
+    )]
+    pub card: Account<'info, Card>,
+    #[account(
+        init,
+        payer = owner,
+        mint::decimals = 0,
+        mint::authority = owner,
+        mint::freeze_authority = owner,
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    /// CHECK: Metaplex metadata PDA, created by the token metadata program
+    pub metadata: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref(), b"edition"],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    /// CHECK: Metaplex master edition PDA, created by the token metadata program
+    pub master_edition: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"card_authority"],
         bump
     )]
+    /// CHECK: Program PDA that holds update authority over card metadata
+    pub card_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLmsr<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + LmsrState::INIT_SPACE,
+        seeds = [b"lmsr", market.key().as_ref()],
+        bump
+    )]
+    pub lmsr_state: Account<'info, LmsrState>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding bets
+    pub vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteLmsr<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        seeds = [b"lmsr", market.key().as_ref()],
+        bump = lmsr_state.bump
+    )]
+    pub lmsr_state: Account<'info, LmsrState>,
+}
+
+#[derive(Accounts)]
+pub struct TradeLmsr<'info> {
     pub market: Account<'info, Market>,
     #[account(mut)]
     pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        seeds = [b"lmsr", market.key().as_ref()],
+        bump = lmsr_state.bump
+    )]
+    pub lmsr_state: Account<'info, LmsrState>,
+    #[account(
+        init_if_needed,
+        payer = trader,
+        space = 8 + LmsrPosition::INIT_SPACE,
+        seeds = [b"lmsr_position", market.key().as_ref(), trader.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, LmsrPosition>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding bets
+    pub vault: AccountInfo<'info>,
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub trader: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLmsrWinnings<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        seeds = [b"lmsr", market.key().as_ref()],
+        bump = lmsr_state.bump
+    )]
+    pub lmsr_state: Account<'info, LmsrState>,
+    #[account(
+        mut,
+        seeds = [b"lmsr_position", market.key().as_ref(), trader.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, LmsrPosition>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding bets
+    pub vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub trader: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseLmsr<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"lmsr", market.key().as_ref()],
+        bump = lmsr_state.bump
+    )]
+    pub lmsr_state: Account<'info, LmsrState>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding bets
+    pub vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeOrderBook<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<OrderBook>(),
+        seeds = [b"order_book", market.key().as_ref()],
+        bump
+    )]
+    pub order_book: AccountLoader<'info, OrderBook>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<EventQueue>(),
+        seeds = [b"event_queue", market.key().as_ref()],
+        bump
+    )]
+    pub event_queue: AccountLoader<'info, EventQueue>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct PlaceBet<'info> {
-    #[account(mut)]
+pub struct PlaceOrder<'info> {
     pub market: Account<'info, Market>,
-    #[account(mut)]
-    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        seeds = [b"order_book", market.key().as_ref()],
+        bump
+    )]
+    pub order_book: AccountLoader<'info, OrderBook>,
     #[account(
         init_if_needed,
-        payer = bettor,
-        space = 8 + Bet::INIT_SPACE,
-        seeds = [b"bet", market.key().as_ref(), bettor.key().as_ref()],
+        payer = owner,
+        space = 8 + BookPosition::INIT_SPACE,
+        seeds = [b"book_position", market.key().as_ref(), owner.key().as_ref()],
         bump
     )]
-    pub bet: Account<'info, Bet>,
+    pub position: Account<'info, BookPosition>,
     #[account(
         mut,
         seeds = [b"vault", market.key().as_ref()],
@@ -617,52 +4578,70 @@ pub struct PlaceBet<'info> {
     /// CHECK: Vault PDA for holding bets
     pub vault: AccountInfo<'info>,
     #[account(mut)]
-    pub bettor: Signer<'info>,
+    pub owner: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ResolveMarket<'info> {
-    #[account(mut)]
+pub struct CancelOrder<'info> {
     pub market: Account<'info, Market>,
-    pub authority: Signer<'info>,
-}
-
-#[derive(Accounts)]
-pub struct ResolveMarketWithOracle<'info> {
+    #[account(
+        mut,
+        seeds = [b"order_book", market.key().as_ref()],
+        bump
+    )]
+    pub order_book: AccountLoader<'info, OrderBook>,
+    #[account(
+        mut,
+        seeds = [b"vault", market.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding bets
+    pub vault: AccountInfo<'info>,
     #[account(mut)]
-    pub market: Account<'info, Market>,
-    /// CHECK: Pyth price feed account, validated in instruction
-    pub price_feed: AccountInfo<'info>,
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ResolveMarketSports<'info> {
-    #[account(mut)]
+pub struct MatchOrders<'info> {
     pub market: Account<'info, Market>,
-    pub authority: Signer<'info>,
-}
-
-#[derive(Accounts)]
-pub struct ResolveMarketWeather<'info> {
     #[account(mut)]
-    pub market: Account<'info, Market>,
-    pub authority: Signer<'info>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        seeds = [b"order_book", market.key().as_ref()],
+        bump
+    )]
+    pub order_book: AccountLoader<'info, OrderBook>,
+    #[account(
+        mut,
+        seeds = [b"event_queue", market.key().as_ref()],
+        bump
+    )]
+    pub event_queue: AccountLoader<'info, EventQueue>,
 }
 
 #[derive(Accounts)]
-pub struct ResolveMarketSocial<'info> {
-    #[account(mut)]
+pub struct ConsumeEvents<'info> {
     pub market: Account<'info, Market>,
-    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"event_queue", market.key().as_ref()],
+        bump
+    )]
+    pub event_queue: AccountLoader<'info, EventQueue>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimWinnings<'info> {
-    #[account(mut)]
+pub struct SettleBookFunds<'info> {
     pub market: Account<'info, Market>,
-    #[account(mut)]
-    pub bet: Account<'info, Bet>,
+    #[account(
+        mut,
+        seeds = [b"book_position", market.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, BookPosition>,
     #[account(
         mut,
         seeds = [b"vault", market.key().as_ref()],
@@ -671,15 +4650,24 @@ pub struct ClaimWinnings<'info> {
     /// CHECK: Vault PDA for holding bets
     pub vault: AccountInfo<'info>,
     #[account(mut)]
-    pub bettor: Signer<'info>,
+    pub owner: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CollectPlatformFee<'info> {
-    #[account(mut)]
+pub struct ClaimBookWinnings<'info> {
     pub market: Account<'info, Market>,
-    pub platform: Account<'info, Platform>,
+    #[account(
+        seeds = [b"event_queue", market.key().as_ref()],
+        bump
+    )]
+    pub event_queue: AccountLoader<'info, EventQueue>,
+    #[account(
+        mut,
+        seeds = [b"book_position", market.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, BookPosition>,
     #[account(
         mut,
         seeds = [b"vault", market.key().as_ref()],
@@ -688,99 +4676,548 @@ pub struct CollectPlatformFee<'info> {
     /// CHECK: Vault PDA for holding bets
     pub vault: AccountInfo<'info>,
     #[account(mut)]
-    /// CHECK: Treasury account from platform
-    pub treasury: AccountInfo<'info>,
-    pub authority: Signer<'info>,
+    pub owner: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
-#[derive(Accounts)]
-pub struct Battle<'info> {
-    #[account(mut)]
-    pub market: Account<'info, Market>,
-    #[account(mut)]
-    pub platform: Account<'info, Platform>,
-    pub card: Account<'info, Card>,
-    #[account(
-        constraint = card_token_account.mint == card.mint,
-        constraint = card_token_account.owner == player.key()
-    )]
-    pub card_token_account: Account<'info, TokenAccount>,
-    #[account(
-        init_if_needed,
-        payer = player,
-        space = 8 + Bet::INIT_SPACE,
-        seeds = [b"bet", market.key().as_ref(), player.key().as_ref()],
-        bump
-    )]
-    pub bet: Account<'info, Bet>,
-    #[account(
-        mut,
-        seeds = [b"vault", market.key().as_ref()],
-        bump
-    )]
-    /// CHECK: Vault PDA for holding bets
-    pub vault: AccountInfo<'info>,
-    #[account(mut)]
-    pub player: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[account]
+#[derive(InitSpace)]
+pub struct Card {
+    pub mint: Pubkey,
+    pub owner: Pubkey,             // Mirrors the token holder; refreshed by battle and sync_card_owner
+    pub power: u8,
+    pub rarity: u8,
+    pub multiplier: u64,
+    pub wins: u64,
+    pub losses: u64,
+    pub active_battles: u8,        // Battle bets not yet settled
+    pub last_settled_at: i64,      // Start of the current cooldown
+    pub experience: u64,           // Unspent XP from settled wins
+    pub level: u8,
+    pub season_id: u32,            // Season the card was minted in
+    pub bump: u8,
+}
+
+// Head-to-head duel on a market outcome; holds both stakes until settlement
+#[account]
+#[derive(InitSpace)]
+pub struct Duel {
+    pub market: Pubkey,
+    pub challenger: Pubkey,
+    pub challenger_card: Pubkey,   // Card PDA
+    pub challenger_prediction: bool, // The opponent holds the other side
+    pub opponent: Option<Pubkey>,  // None while the duel is open
+    pub opponent_card: Option<Pubkey>,
+    pub stake: u64,                // Lamports escrowed by each player
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+// Card season: per-rarity supply caps while active, and a snapshot of
+// platform totals once it ends so rewards can be scoped to the season
+#[account]
+#[derive(InitSpace)]
+pub struct Season {
+    pub id: u32,
+    #[max_len(MAX_RARITY_TIERS)]
+    pub supply_caps: Vec<u32>,     // Indexed by rarity
+    #[max_len(MAX_RARITY_TIERS)]
+    pub minted: Vec<u32>,          // Cards minted (or reserved by fusions) per rarity
+    pub start_time: i64,
+    pub end_time: i64,             // Mints close at this time
+    pub start_volume: u64,         // Platform volume when the season opened
+    pub ended: bool,
+    pub ended_at: i64,
+    pub end_volume: u64,           // Snapshot of Platform.total_volume at season end
+    pub end_bonus_paid: u64,       // Snapshot of Platform.total_bonus_paid at season end
+    pub bump: u8,
+}
+
+impl Season {
+    pub fn has_supply(&self, rarity: usize) -> bool {
+        match (self.minted.get(rarity), self.supply_caps.get(rarity)) {
+            (Some(minted), Some(cap)) => minted < cap,
+            _ => false,
+        }
+    }
+
+    // Counts one card of `rarity` against the active season's cap
+    pub fn is_open(&self, now: i64) -> bool {
+        !self.ended && now < self.end_time
+    }
+
+    pub fn has_any_supply(&self) -> bool {
+        (0..self.supply_caps.len()).any(|rarity| self.has_supply(rarity))
+    }
+
+    pub fn reserve(&mut self, rarity: u8) -> Result<()> {
+        require!(self.is_open(Clock::get()?.unix_timestamp), PredictionError::SeasonNotActive);
+        require!(self.has_supply(rarity as usize), PredictionError::SupplyCapReached);
+        self.minted[rarity as usize] += 1;
+        Ok(())
+    }
+}
+
+// Top season PnL per wallet; holds the season's prize pool in lamports
+#[account]
+#[derive(InitSpace)]
+pub struct Leaderboard {
+    pub season_id: u32,
+    #[max_len(LEADERBOARD_SIZE)]
+    pub entries: Vec<LeaderboardEntry>, // Sorted by pnl, highest first
+    pub prize_pool: u64,           // Lamports funded from platform fees
+    pub distributed: bool,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct LeaderboardEntry {
+    pub owner: Pubkey,
+    pub pnl: i64,
+}
+
+impl Leaderboard {
+    // Only profitable wallets rank; a wallet that drops to zero or below leaves the board
+    pub fn submit(&mut self, owner: Pubkey, pnl: i64) {
+        self.entries.retain(|entry| entry.owner != owner);
+        if pnl <= 0 {
+            return;
+        }
+        let rank = self.entries.partition_point(|entry| entry.pnl >= pnl);
+        if rank < LEADERBOARD_SIZE {
+            self.entries.insert(rank, LeaderboardEntry { owner, pnl });
+            self.entries.truncate(LEADERBOARD_SIZE);
+        }
+    }
+}
+
+// Rental listing; owns the escrowed card token while the card is listed
+#[account]
+#[derive(InitSpace)]
+pub struct CardListing {
+    pub card: Pubkey,
+    pub mint: Pubkey,
+    pub lender: Pubkey,
+    pub rent_fee: u64,             // Lamports per rental period, paid to the lender
+    pub duration: i64,             // Seconds per rental period
+    pub renter: Option<Pubkey>,    // Latest renter; the rental is live until rented_until
+    pub rented_until: i64,
+    pub bump: u8,
+}
+
+#[event]
+pub struct MarketCreated {
+    pub market: Pubkey,
+    pub index: u64,
+    pub creator: Pubkey,
+    pub question: String,
+    pub category: MarketCategory,
+    pub oracle_source: OracleSource,
+    pub oracle_data_type: OracleDataType,
+    pub end_time: i64,
+    pub yes_mint: Pubkey,
+    pub no_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+// Pool totals after the bet; implied YES odds are total_yes / (total_yes + total_no)
+#[event]
+pub struct BetPlaced {
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub prediction: bool,
+    pub amount: u64,
+    pub card: Option<Pubkey>,      // Card mint for battle bets
+    pub total_yes_amount: u64,
+    pub total_no_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetWithdrawn {
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub prediction: bool,
+    pub amount: u64,               // Stake withdrawn, including the penalty
+    pub penalty: u64,              // Forfeited to the pool
+    pub total_yes_amount: u64,
+    pub total_no_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketResolved {
+    pub market: Pubkey,
+    pub oracle_source: OracleSource,
+    pub outcome: Option<bool>,
+    pub total_yes_amount: u64,
+    pub total_no_amount: u64,
+    pub forfeited_amount: u64,
+    pub strike_price: Option<i64>, // Oracle price for price markets
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WinningsClaimed {
+    pub market: Pubkey,
+    pub claimant: Pubkey,
+    pub position_mint: Pubkey,
+    pub redeemed: u64,             // Position tokens burned
+    pub winnings: u64,
+    pub bonus: u64,                // Card bonus paid from the bonus pool
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeCollected {
+    pub market: Pubkey,
+    pub treasury: Pubkey,
+    pub amount: u64,
+    pub total_pool: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CardMinted {
+    pub card: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub rarity: u8,
+    pub power: u8,
+    pub multiplier: u64,
+    pub season_id: u32,
+    pub fused: bool,               // Produced by fuse_cards
+    pub timestamp: i64,
+}
+
+// Full record snapshot whenever a battle or duel settles or the card levels up
+#[event]
+pub struct CardStatsUpdated {
+    pub card: Pubkey,
+    pub wins: u64,
+    pub losses: u64,
+    pub experience: u64,
+    pub level: u8,
+    pub active_battles: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CardExperienceGained {
+    pub card: Pubkey,
+    pub market: Pubkey,
+    pub experience: u64,
+    pub total_experience: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CardLeveledUp {
+    pub card: Pubkey,
+    pub level: u8,
+    pub power: u8,
+    pub multiplier: u64,
+    pub remaining_experience: u64,
+    pub timestamp: i64,
+}
+
+// Prices are the instantaneous LMSR prices after the trade, scaled by lmsr::FIXED_ONE
+#[event]
+pub struct LmsrTraded {
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    pub outcome: bool,             // true = YES shares
+    pub buy: bool,                 // false for a sell
+    pub shares: u64,
+    pub amount: u64,               // Cost of a buy, proceeds of a sell
+    pub yes_price: u64,
+    pub no_price: u64,
+    pub yes_shares: u64,           // Outstanding shares after the trade
+    pub no_shares: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderPlaced {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub side: OrderSide,
+    pub price: u64,                // YES price per share, out of BOOK_SHARE_LAMPORTS
+    pub quantity: u64,
+    pub escrow: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderCancelled {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub side: OrderSide,
+    pub remaining_quantity: u64,
+    pub refund: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderFilled {
+    pub market: Pubkey,
+    pub bidder: Pubkey,
+    pub asker: Pubkey,
+    pub bid_order_id: u64,
+    pub ask_order_id: u64,
+    pub taker_side: OrderSide,     // Side of the newer order, which crossed the book
+    pub price: u64,                // YES price per share, out of BOOK_SHARE_LAMPORTS
+    pub quantity: u64,
+    pub timestamp: i64,
+}
+
+// LMSR and order-book shares redeemed after resolution
+#[event]
+pub struct SharesRedeemed {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub outcome: Option<bool>,     // None for a voided market's cost-basis refund
+    pub yes_shares: u64,
+    pub no_shares: u64,
+    pub payout: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DuelCreated {
+    pub duel: Pubkey,
+    pub market: Pubkey,
+    pub challenger: Pubkey,
+    pub card: Pubkey,
+    pub prediction: bool,
+    pub stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DuelAccepted {
+    pub duel: Pubkey,
+    pub market: Pubkey,
+    pub opponent: Pubkey,
+    pub card: Pubkey,
+    pub prediction: bool,          // Always the challenger's opposite
+    pub stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DuelCancelled {
+    pub duel: Pubkey,
+    pub challenger: Pubkey,
+    pub stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DuelSettled {
+    pub duel: Pubkey,
+    pub market: Pubkey,
+    pub outcome: Option<bool>,
+    pub winner: Option<Pubkey>,    // None when both stakes were refunded
+    pub payout: u64,               // Paid to the winner, or refunded to each player
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CardListed {
+    pub card: Pubkey,
+    pub mint: Pubkey,
+    pub lender: Pubkey,
+    pub rent_fee: u64,
+    pub duration: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CardRented {
+    pub card: Pubkey,
+    pub mint: Pubkey,
+    pub lender: Pubkey,
+    pub renter: Pubkey,
+    pub rent_fee: u64,
+    pub rented_until: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CardReclaimed {
+    pub card: Pubkey,
+    pub mint: Pubkey,
+    pub lender: Pubkey,
+    pub timestamp: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CardConfig {
+    pub authority: Pubkey,
+    pub minter: Pubkey,            // Mints hand-picked stats, requests for free
+    pub randomness_oracle: Pubkey, // Fulfils randomness for card mint requests
+    pub mint_price: u64,           // Lamports for anyone else; 0 disables paid mints
+    #[max_len(MAX_RARITY_TIERS)]
+    pub tiers: Vec<RarityTier>,    // Indexed by Card.rarity
+    #[max_len(MAX_URI_TEMPLATE_LEN)]
+    pub uri_template: String,      // e.g. "https://cards.example/{rarity}.json"
+    pub battle_cooldown: i64,      // Seconds a card rests after each settled battle
+    pub level_curve: LevelCurve,
+    pub current_season: u32,       // 0 until the first season starts
+    pub season_active: bool,       // Cards can only be minted during a season
+    pub bump: u8,
+}
+
+impl CardConfig {
+    pub fn validate_stats(&self, rarity: u8, power: u8, multiplier: u64) -> Result<()> {
+        let tier = self.tiers
+            .get(rarity as usize)
+            .ok_or(PredictionError::InvalidRarity)?;
+        require!(
+            power >= tier.min_power && power <= tier.max_power,
+            PredictionError::CardStatsOutOfRange
+        );
+        require!(
+            multiplier >= tier.min_multiplier && multiplier <= tier.max_multiplier,
+            PredictionError::CardStatsOutOfRange
+        );
+        Ok(())
+    }
+
+    // Weighted rarity roll, then uniform power and multiplier within the tier
+    // Tiers whose season supply is exhausted drop out of the roll
+    pub fn roll(&self, randomness: &[u8; 32], season: &Season) -> Result<(u8, u8, u64)> {
+        let word = |i: usize| u64::from_le_bytes(randomness[i * 8..(i + 1) * 8].try_into().unwrap());
+        let weight = |index: usize| -> u64 {
+            if season.has_supply(index) { self.tiers[index].weight as u64 } else { 0 }
+        };
+
+        let total_weight: u64 = (0..self.tiers.len()).map(weight).sum();
+        require!(total_weight > 0, PredictionError::SupplyCapReached);
+
+        let mut pick = word(0) % total_weight;
+        let mut rarity = 0;
+        for index in 0..self.tiers.len() {
+            if pick < weight(index) {
+                rarity = index;
+                break;
+            }
+            pick -= weight(index);
+        }
+
+        let (power, multiplier) = self.roll_stats(rarity as u8, randomness)?;
+        Ok((rarity as u8, power, multiplier))
+    }
+
+    // Uniform power and multiplier within a fixed tier
+    pub fn roll_stats(&self, rarity: u8, randomness: &[u8; 32]) -> Result<(u8, u64)> {
+        let word = |i: usize| u64::from_le_bytes(randomness[i * 8..(i + 1) * 8].try_into().unwrap());
+
+        let tier = self.tiers
+            .get(rarity as usize)
+            .ok_or(PredictionError::InvalidRarity)?;
+        let power_range = (tier.max_power - tier.min_power) as u64 + 1;
+        let power = tier.min_power + (word(1) % power_range) as u8;
+        let multiplier = match (tier.max_multiplier - tier.min_multiplier).checked_add(1) {
+            Some(range) => tier.min_multiplier + word(2) % range,
+            None => tier.min_multiplier.wrapping_add(word(2)),
+        };
+
+        Ok((power, multiplier))
+    }
+
+    // Fused cards roll within the next tier but never come out weaker than
+    // the best card that went in, up to that tier's ceiling
+    pub fn roll_fusion(&self, fusion: &FusedCards, randomness: &[u8; 32]) -> Result<(u8, u8, u64)> {
+        let (rolled_power, rolled_multiplier) = self.roll_stats(fusion.rarity, randomness)?;
+        let tier = &self.tiers[fusion.rarity as usize];
+        let power = rolled_power.max(fusion.power.min(tier.max_power));
+        let multiplier = rolled_multiplier.max(fusion.multiplier.min(tier.max_multiplier));
+
+        Ok((fusion.rarity, power, multiplier))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct LevelCurve {
+    pub base_xp: u64,              // XP to go from level 0 to 1
+    pub growth_bps: u16,           // Extra XP required per level, compounding
+    pub power_per_level: u8,
+    pub multiplier_per_level: u64, // Same scale as Card.multiplier (1000 = 1x)
+    pub max_level: u8,
 }
 
-#[derive(Accounts)]
-pub struct UpdateCardStats<'info> {
-    #[account(mut)]
-    pub card: Account<'info, Card>,
-    #[account(
-        constraint = card_token_account.mint == card.mint,
-        constraint = card_token_account.owner == owner.key()
-    )]
-    pub card_token_account: Account<'info, TokenAccount>,
-    pub owner: Signer<'info>,
+impl Default for LevelCurve {
+    fn default() -> Self {
+        Self {
+            base_xp: 100,
+            growth_bps: 5000,
+            power_per_level: 1,
+            multiplier_per_level: 25,
+            max_level: 10,
+        }
+    }
 }
 
-#[derive(Accounts)]
-pub struct MintCard<'info> {
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + Card::INIT_SPACE,
-        seeds = [b"card", mint.key().as_ref()],
-        bump
-    )]
-    pub card: Account<'info, Card>,
-    #[account(
-        init,
-        payer = payer,
-        mint::decimals = 0,
-        mint::authority = payer,
-        mint::freeze_authority = payer,
-    )]
-    pub mint: Account<'info, Mint>,
-    #[account(
-        init,
-        payer = payer,
-        associated_token::mint = mint,
-        associated_token::authority = owner,
-    )]
-    pub token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub owner: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
+impl LevelCurve {
+    pub fn xp_for_next_level(&self, level: u8) -> u64 {
+        let mut required = self.base_xp as u128;
+        for _ in 0..level {
+            required = required * (10000 + self.growth_bps as u128) / 10000;
+        }
+        u64::try_from(required).unwrap_or(u64::MAX)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct RarityTier {
+    pub weight: u16,               // Relative odds of rolling this tier
+    pub max_active_battles: u8,    // Unsettled battles a card may join at once
+    pub min_power: u8,
+    pub max_power: u8,
+    pub min_multiplier: u64,
+    pub max_multiplier: u64,
 }
 
 #[account]
 #[derive(InitSpace)]
-pub struct Card {
-    pub mint: Pubkey,
+pub struct CardMintRequest {
     pub owner: Pubkey,
-    pub power: u8,
-    pub rarity: u8,
-    pub multiplier: u64,
+    pub seed: [u8; 32],
+    pub randomness: Pubkey,
+    pub requested_slot: u64,
+    pub fusion: Option<FusedCards>, // Set when the request came from fuse_cards
+    pub season_id: u32,            // Season the card is minted from
+    pub paid: u64,                 // Mint price escrowed on this account until fulfilment
+    pub bump: u8,
+}
+
+// What a fusion burned, carried into the card it produces
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct FusedCards {
+    pub season_id: u32,            // Season whose supply the result was reserved from
+    pub rarity: u8,                // Tier of the resulting card
+    pub power: u8,                 // Best power among the burned cards
+    pub multiplier: u64,           // Best multiplier among the burned cards
     pub wins: u64,
     pub losses: u64,
+    pub experience: u64,           // Unspent XP pooled from the burned cards
+}
+
+// VRF-style randomness account: created alongside a request, written once
+// by the configured oracle, and consumed by the request it names
+#[account]
+#[derive(InitSpace)]
+pub struct Randomness {
+    pub consumer: Pubkey,
+    pub oracle: Pubkey,
+    pub seed: [u8; 32],
+    pub value: [u8; 32],
+    pub fulfilled_slot: u64,       // 0 until the oracle reveals
     pub bump: u8,
 }
 
@@ -791,12 +5228,24 @@ pub struct Platform {
     pub treasury: Pubkey,
     pub total_markets: u64,
     pub total_volume: u64,
+    pub early_exit_penalty_bps: u16,
+    pub total_bonus_funded: u64,   // Lamports deposited into the card bonus pool
+    pub total_bonus_paid: u64,     // Card bonuses paid out on top of base winnings
+    pub current_season: u32,       // Active card season, 0 between seasons
+    pub leaderboard_fee_bps: u16,  // Share of collected fees funding the season leaderboard
+    pub referral_fee_bps: u16,     // Share of a referred user's fee credited to the referrer
+    pub creator_stake_base: u64,   // Creator stake for a market, before reputation discounts
+    pub manual_market_bond: u64,   // Bond for OracleSource::Manual markets, slashed if resolved late
+    pub resolution_grace_period: i64, // Seconds after end_time before an unresolved market can be voided
+    pub leaderboard_settlement_period: i64, // Seconds after a season ends before its prizes are paid
     pub bump: u8,
 }
 
 #[account]
 #[derive(InitSpace)]
 pub struct Market {
+    pub index: u64,                       // Platform-wide creation order, the PDA seed
+    pub season_id: u32,                   // Season active at creation; its leaderboard counts this market's PnL
     pub authority: Pubkey,
     pub creator: Pubkey,
     #[max_len(100)]
@@ -804,11 +5253,24 @@ pub struct Market {
     #[max_len(200)]
     pub description: String,
     pub end_time: i64,
+    pub resolution_deadline: i64,         // Unresolved past this, anyone can void the market
     pub created_at: i64,
     pub resolved: bool,
     pub outcome: Option<bool>,
     pub total_yes_amount: u64,
     pub total_no_amount: u64,
+    pub forfeited_amount: u64,            // Early-exit penalties left in the pool
+    pub fee_collected: bool,
+    pub referral_paid: u64,               // Referral rewards paid out of the platform fee
+    pub referred_yes_amount: u64,         // YES stake from wallets with a referrer
+    pub referred_no_amount: u64,          // NO stake from wallets with a referrer
+    pub creator_stake: u64,               // Locked on the CreatorProfile until resolution
+    pub creator_bond: u64,                // Manual markets only; 0 once returned or slashed
+    pub stake_released: bool,
+    pub resolved_at: i64,
+    pub dispute: DisputeStatus,
+    pub yes_mint: Pubkey,                 // Transferable YES position token
+    pub no_mint: Pubkey,                  // Transferable NO position token
     pub category: MarketCategory,
     pub bump: u8,
     
@@ -843,16 +5305,431 @@ pub struct Market {
     pub actual_value: Option<u64>,         // Recorded value
 }
 
+impl Market {
+    // Address of the market created with the given index (Platform.total_markets
+    // at creation time), for clients enumerating markets
+    pub fn pda(index: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"market", index.to_le_bytes().as_ref()], &crate::ID)
+    }
+
+    // Everything the pari-mutuel payout is split from
+    pub fn total_pool(&self) -> u64 {
+        self.total_yes_amount
+            .checked_add(self.total_no_amount).unwrap()
+            .checked_add(self.forfeited_amount).unwrap()
+    }
+
+    // What `stake` on the winning side is paid after the platform fee
+    pub fn winning_payout(&self, stake: u64, outcome: bool) -> u64 {
+        let winning_pool = if outcome { self.total_yes_amount } else { self.total_no_amount };
+        if winning_pool == 0 {
+            return 0;
+        }
+        let total_pool = self.total_pool();
+        let platform_fee = total_pool
+            .checked_mul(PLATFORM_FEE_BPS).unwrap()
+            .checked_div(10000).unwrap();
+        (stake as u128)
+            .checked_mul(total_pool.checked_sub(platform_fee).unwrap() as u128).unwrap()
+            .checked_div(winning_pool as u128).unwrap() as u64
+    }
+
+    pub fn record_referred_stake(&mut self, amount: u64, prediction: bool) {
+        if prediction {
+            self.referred_yes_amount = self.referred_yes_amount.checked_add(amount).unwrap();
+        } else {
+            self.referred_no_amount = self.referred_no_amount.checked_add(amount).unwrap();
+        }
+    }
+
+    // Part of the platform fee owed to referrers: their share of the fee
+    // carried by the referred stake on the winning side
+    pub fn referral_reserve(&self, referral_fee_bps: u16) -> u64 {
+        let Some(outcome) = self.outcome else {
+            return 0;
+        };
+        let (winning_pool, referred) = if outcome {
+            (self.total_yes_amount, self.referred_yes_amount)
+        } else {
+            (self.total_no_amount, self.referred_no_amount)
+        };
+        if winning_pool == 0 {
+            return 0;
+        }
+        let platform_fee = self.total_pool()
+            .checked_mul(PLATFORM_FEE_BPS).unwrap()
+            .checked_div(10000).unwrap();
+        (platform_fee as u128)
+            .checked_mul(referred.min(winning_pool) as u128).unwrap()
+            .checked_div(winning_pool as u128).unwrap()
+            .checked_mul(referral_fee_bps as u128).unwrap()
+            .checked_div(10000).unwrap() as u64
+    }
+}
+
+// Market creator's resolution track record; also holds locked creator stakes
+#[account]
+#[derive(InitSpace)]
+pub struct CreatorProfile {
+    pub owner: Pubkey,
+    pub markets_created: u32,
+    pub resolved_on_time: u32,     // Resolved by the market's resolution_deadline
+    pub resolved_late: u32,
+    pub disputed: u32,
+    pub overturned: u32,           // Disputes that reversed the resolution
+    pub cancelled: u32,
+    pub locked_stake: u64,         // Lamports held on this account for open markets
+    pub bump: u8,
+}
+
+impl CreatorProfile {
+    pub fn init_if_new(&mut self, owner: Pubkey, bump: u8) {
+        if self.owner == Pubkey::default() {
+            self.owner = owner;
+            self.bump = bump;
+        }
+    }
+
+    // Net on-time resolutions, after penalties for bad outcomes
+    pub fn reputation(&self) -> u64 {
+        let penalties = self.resolved_late as u64
+            + self.disputed as u64
+            + 2 * (self.overturned as u64 + self.cancelled as u64);
+        (self.resolved_on_time as u64).saturating_sub(penalties)
+    }
+
+    // Stake falls as base * RAMP / (RAMP + reputation)
+    pub fn required_stake(&self, stake_base: u64) -> u64 {
+        ((stake_base as u128) * CREATOR_REPUTATION_RAMP as u128
+            / (CREATOR_REPUTATION_RAMP + self.reputation()) as u128) as u64
+    }
+}
+
+// Lifetime betting record for one wallet, across all markets
+#[account]
+#[derive(InitSpace)]
+pub struct UserProfile {
+    pub owner: Pubkey,
+    pub total_wagered: u64,        // Lamports staked through place_bet and battle
+    pub total_won: u64,            // Settled payouts plus card bonuses claimed
+    pub markets_entered: u32,
+    pub bets_won: u32,             // Settled bets that paid out more than they staked
+    pub bets_lost: u32,            // Settled bets that paid out less than they staked
+    pub win_streak: u32,
+    pub best_win_streak: u32,
+    pub realized_pnl: i64,         // Settled payouts and bonuses minus stakes
+    pub season_id: u32,            // Latest season the wallet realised PnL in
+    pub season_pnl: i64,           // Realized PnL on season_id's markets, ranked on its leaderboard
+    pub prev_season_id: u32,       // Season before season_id, still open to late settlements
+    pub prev_season_pnl: i64,
+    pub referrer: Option<Pubkey>,  // Set on the wallet's first bet
+    pub referral_rewards: u64,     // Claimable lamports, held on this account
+    pub total_referral_earned: u64,
+    pub bump: u8,
+}
+
+impl UserProfile {
+    pub fn init_if_new(&mut self, owner: Pubkey, bump: u8) {
+        if self.owner == Pubkey::default() {
+            self.owner = owner;
+            self.bump = bump;
+        }
+    }
+
+    // A referrer can only be attached to a wallet's very first bet
+    pub fn attach_referrer(&mut self, referrer_profile: Option<&UserProfile>) -> Result<()> {
+        if let Some(referrer_profile) = referrer_profile {
+            require!(self.total_wagered == 0, PredictionError::ReferrerAlreadySet);
+            require!(
+                referrer_profile.owner != self.owner,
+                PredictionError::InvalidReferrer
+            );
+            self.referrer = Some(referrer_profile.owner);
+        }
+        Ok(())
+    }
+
+    pub fn record_wager(&mut self, amount: u64, new_market: bool) {
+        self.total_wagered = self.total_wagered.saturating_add(amount);
+        if new_market {
+            self.markets_entered = self.markets_entered.saturating_add(1);
+        }
+    }
+
+    // `payout` is what the bet's winning side is owed, `cost` both sides'
+    // stakes; a hedged bet counts as won or lost on its net result
+    pub fn record_settlement(&mut self, payout: u64, cost: u64, season_id: u32) {
+        let pnl = payout as i64 - cost as i64;
+        self.total_won = self.total_won.saturating_add(payout);
+        self.realized_pnl = self.realized_pnl.saturating_add(pnl);
+        self.record_season_pnl(season_id, pnl);
+        if pnl > 0 {
+            self.bets_won = self.bets_won.saturating_add(1);
+            self.win_streak = self.win_streak.saturating_add(1);
+            self.best_win_streak = self.best_win_streak.max(self.win_streak);
+        } else if pnl < 0 {
+            self.bets_lost = self.bets_lost.saturating_add(1);
+            self.win_streak = 0;
+        }
+    }
+
+    pub fn record_bonus(&mut self, bonus: u64, season_id: u32) {
+        self.total_won = self.total_won.saturating_add(bonus);
+        self.realized_pnl = self.realized_pnl.saturating_add(bonus as i64);
+        self.record_season_pnl(season_id, bonus as i64);
+    }
+
+    // PnL counts toward the season its market was created in. The previous
+    // season stays open so bets settled during its leaderboard's settlement
+    // period still land there after the wallet has moved on.
+    fn record_season_pnl(&mut self, season_id: u32, pnl: i64) {
+        if season_id == 0 {
+            return;
+        }
+        if season_id > self.season_id {
+            self.prev_season_id = self.season_id;
+            self.prev_season_pnl = self.season_pnl;
+            self.season_id = season_id;
+            self.season_pnl = 0;
+        }
+        if season_id == self.season_id {
+            self.season_pnl = self.season_pnl.saturating_add(pnl);
+        } else if season_id == self.prev_season_id {
+            self.prev_season_pnl = self.prev_season_pnl.saturating_add(pnl);
+        }
+    }
+
+    pub fn season_pnl_for(&self, season_id: u32) -> Option<i64> {
+        if season_id == self.season_id {
+            Some(self.season_pnl)
+        } else if season_id == self.prev_season_id {
+            Some(self.prev_season_pnl)
+        } else {
+            None
+        }
+    }
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct Bet {
     pub market: Pubkey,
     pub bettor: Pubkey,
-    pub amount: u64,
-    pub prediction: bool,
-    pub claimed: bool,
+    pub yes_amount: u64,           // Stake on YES, tracked independently so bettors can hedge
+    pub no_amount: u64,            // Stake on NO
     pub card_mint: Option<Pubkey>,
-    pub card_multiplier: u64,
+    pub card_multiplier: u64,      // Card multiplier when the card joined the bet
+    pub card_yes_amount: u64,      // Part of yes_amount staked through battle
+    pub card_no_amount: u64,       // Part of no_amount staked through battle
+    pub bonus_claimed: bool,
+    pub bonus_paid: u64,           // Paid from the bonus pool, not the market vault
+    pub settled: bool,             // Result recorded on the profile (and card) after resolution
+}
+
+impl Bet {
+    // (winning side, losing side) stakes for a resolved outcome
+    pub fn stakes(&self, outcome: bool) -> (u64, u64) {
+        if outcome {
+            (self.yes_amount, self.no_amount)
+        } else {
+            (self.no_amount, self.yes_amount)
+        }
+    }
+
+    // (winning side, losing side) stakes placed through battle with the card
+    pub fn card_stakes(&self, outcome: bool) -> (u64, u64) {
+        if outcome {
+            (self.card_yes_amount, self.card_no_amount)
+        } else {
+            (self.card_no_amount, self.card_yes_amount)
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LmsrState {
+    pub market: Pubkey,
+    pub creator: Pubkey,
+    pub liquidity_b: u64,          // LMSR liquidity parameter, in lamports
+    pub yes_shares: u64,           // Outstanding YES shares (1 lamport payout each)
+    pub no_shares: u64,            // Outstanding NO shares
+    pub subsidy: u64,              // Creator-funded b * ln(2), rounded up
+    pub collected: u64,            // Net lamports paid in by traders
+    pub total_cost_basis: u64,     // Sum of LmsrPosition.cost_basis, refunded if the market is voided
+    pub settled: bool,             // Creator has withdrawn the remainder
+    pub bump: u8,
+}
+
+impl LmsrState {
+    // Owed to traders on a voided market: every cost basis, as far as the
+    // collected lamports and the subsidy can cover it
+    pub fn void_liability(&self) -> u64 {
+        self.total_cost_basis.min(self.subsidy.checked_add(self.collected).unwrap())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LmsrPosition {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub yes_shares: u64,
+    pub no_shares: u64,
+    pub cost_basis: u64,           // Lamports paid for shares, less sale proceeds
+    pub claimed: bool,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct LmsrQuote {
+    pub yes_price: u64,            // Scaled by lmsr::FIXED_ONE
+    pub no_price: u64,             // Scaled by lmsr::FIXED_ONE
+    pub cost: u64,                 // Lamports to buy the quoted shares
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct BookPosition {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub yes_shares: u64,           // Filled bids
+    pub no_shares: u64,            // Filled asks
+    pub free_lamports: u64,        // Unspent escrow released by fills
+    pub cost_basis: u64,           // Lamports paid for filled shares at fill prices
+    pub claimed: bool,
+    pub bump: u8,
+}
+
+#[zero_copy]
+#[derive(Default, Debug)]
+pub struct BookOrder {
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub price: u64,                // Lamports per YES share
+    pub quantity: u64,             // Unfilled shares
+    pub escrow: u64,               // Lamports still locked in the vault
+}
+
+#[account(zero_copy)]
+pub struct OrderBook {
+    pub market: Pubkey,
+    pub next_order_id: u64,
+    pub bid_count: u64,
+    pub ask_count: u64,
+    pub bids: [BookOrder; MAX_BOOK_ORDERS],   // Best (highest) price first
+    pub asks: [BookOrder; MAX_BOOK_ORDERS],   // Best (lowest) price first
+}
+
+impl OrderBook {
+    pub fn orders(&self, side: OrderSide) -> &[BookOrder] {
+        match side {
+            OrderSide::Bid => &self.bids[..self.bid_count as usize],
+            OrderSide::Ask => &self.asks[..self.ask_count as usize],
+        }
+    }
+
+    pub fn find(&self, side: OrderSide, order_id: u64) -> Option<usize> {
+        self.orders(side).iter().position(|order| order.order_id == order_id)
+    }
+
+    // Keep each side sorted by price, then by time
+    pub fn insert(&mut self, side: OrderSide, order: BookOrder) -> Result<()> {
+        let (orders, count) = match side {
+            OrderSide::Bid => (&mut self.bids, &mut self.bid_count),
+            OrderSide::Ask => (&mut self.asks, &mut self.ask_count),
+        };
+        let len = *count as usize;
+        require!(len < MAX_BOOK_ORDERS, PredictionError::OrderBookFull);
+
+        let index = orders[..len]
+            .iter()
+            .position(|resting| match side {
+                OrderSide::Bid => order.price > resting.price,
+                OrderSide::Ask => order.price < resting.price,
+            })
+            .unwrap_or(len);
+        orders.copy_within(index..len, index + 1);
+        orders[index] = order;
+        *count += 1;
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, side: OrderSide, index: usize) {
+        let (orders, count) = match side {
+            OrderSide::Bid => (&mut self.bids, &mut self.bid_count),
+            OrderSide::Ask => (&mut self.asks, &mut self.ask_count),
+        };
+        let len = *count as usize;
+        orders.copy_within(index + 1..len, index);
+        orders[len - 1] = BookOrder::default();
+        *count -= 1;
+    }
+}
+
+#[zero_copy]
+#[derive(Default, Debug)]
+pub struct FillEvent {
+    pub bidder: Pubkey,
+    pub asker: Pubkey,
+    pub bid_order_id: u64,
+    pub ask_order_id: u64,
+    pub price: u64,
+    pub quantity: u64,
+    pub bid_refund: u64,           // Escrow released to the bidder
+    pub ask_refund: u64,           // Escrow released to the asker
+}
+
+#[account(zero_copy)]
+pub struct EventQueue {
+    pub market: Pubkey,
+    pub head: u64,
+    pub count: u64,
+    pub events: [FillEvent; MAX_BOOK_EVENTS],
+}
+
+impl EventQueue {
+    pub fn is_full(&self) -> bool {
+        self.count as usize == MAX_BOOK_EVENTS
+    }
+
+    pub fn push(&mut self, event: FillEvent) -> Result<()> {
+        require!(!self.is_full(), PredictionError::EventQueueFull);
+        let tail = (self.head + self.count) as usize % MAX_BOOK_EVENTS;
+        self.events[tail] = event;
+        self.count += 1;
+        Ok(())
+    }
+
+    pub fn has_fills_for(&self, owner: Pubkey) -> bool {
+        (0..self.count).any(|offset| {
+            let event = &self.events[((self.head + offset) as usize) % MAX_BOOK_EVENTS];
+            event.bidder == owner || event.asker == owner
+        })
+    }
+
+    pub fn peek(&self) -> Option<FillEvent> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(self.events[self.head as usize])
+    }
+
+    pub fn pop(&mut self) {
+        self.events[self.head as usize] = FillEvent::default();
+        self.head = (self.head + 1) % MAX_BOOK_EVENTS as u64;
+        self.count -= 1;
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Default, Debug)]
+pub enum DisputeStatus {
+    #[default]
+    None,
+    Open,                // Flagged; creator stake and bond stay locked until ruled on
+    Upheld,              // The resolution stands
+    Overturned,          // The resolution was wrong
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Default, Debug)]
@@ -903,6 +5780,12 @@ pub enum MetricType {
     Custom,          // Custom metric
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OrderSide {
+    Bid,                 // Buy YES shares
+    Ask,                 // Sell YES shares (buy NO shares)
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
 pub enum MarketCategory {
     Sports,
@@ -955,4 +5838,132 @@ pub enum PredictionError {
     PriceNotAvailable,
     #[msg("Price data is stale (older than 60 seconds)")]
     StalePriceData,
+    #[msg("LMSR liquidity parameter must be greater than zero")]
+    InvalidLiquidity,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Trade price moved beyond the allowed slippage")]
+    SlippageExceeded,
+    #[msg("Not enough shares in position")]
+    InsufficientShares,
+    #[msg("Order price must be between 1 and the share payout")]
+    InvalidOrderPrice,
+    #[msg("Order book side is full")]
+    OrderBookFull,
+    #[msg("Order not found")]
+    OrderNotFound,
+    #[msg("Event queue is full, consume events first")]
+    EventQueueFull,
+    #[msg("Position does not belong to this market")]
+    InvalidPosition,
+    #[msg("Basis points must not exceed 10000")]
+    InvalidBasisPoints,
+    #[msg("Withdrawal exceeds the staked amount")]
+    InsufficientStake,
+    #[msg("No position tokens to redeem")]
+    EmptyPosition,
+    #[msg("Rarity is not a configured tier")]
+    InvalidRarity,
+    #[msg("Card stats are outside the range allowed for this rarity")]
+    CardStatsOutOfRange,
+    #[msg("Randomness has already been fulfilled")]
+    RandomnessAlreadyFulfilled,
+    #[msg("Randomness has not been fulfilled yet")]
+    RandomnessNotFulfilled,
+    #[msg("Card account is required to settle a battle bet")]
+    CardAccountRequired,
+    #[msg("Card does not match the one used in this battle")]
+    CardMismatch,
+    #[msg("Metadata URI template is too long (max 150 characters)")]
+    UriTooLong,
+    #[msg("Battle cooldown cannot be negative")]
+    InvalidCooldown,
+    #[msg("A different card is already attached to this bet")]
+    CardAlreadyAttached,
+    #[msg("Card is cooling down after its last battle")]
+    CardOnCooldown,
+    #[msg("Card has reached its concurrent battle limit")]
+    TooManyActiveBattles,
+    #[msg("Level curve base XP must be greater than zero")]
+    InvalidLevelCurve,
+    #[msg("Card is already at the maximum level")]
+    MaxLevelReached,
+    #[msg("Not enough experience to level up")]
+    NotEnoughExperience,
+    #[msg("Fusion needs distinct cards of a single rarity")]
+    InvalidFusion,
+    #[msg("Cards of the highest rarity cannot be fused")]
+    MaxRarityReached,
+    #[msg("Card is still attached to an unsettled battle")]
+    CardInBattle,
+    #[msg("Duel already has an opponent")]
+    DuelAlreadyAccepted,
+    #[msg("Duel has not been accepted")]
+    DuelNotAccepted,
+    #[msg("Rental duration must be positive")]
+    InvalidRentalTerms,
+    #[msg("Card is currently rented")]
+    CardRented,
+    #[msg("Caller is not renting this card")]
+    NotCardRenter,
+    #[msg("A card season is already active")]
+    SeasonActive,
+    #[msg("No active card season")]
+    SeasonNotActive,
+    #[msg("Season has not reached its end time")]
+    SeasonNotOver,
+    #[msg("Season must follow the current one, cap every tier and end in the future")]
+    InvalidSeason,
+    #[msg("Season supply cap reached for this rarity")]
+    SupplyCapReached,
+    #[msg("The active season's leaderboard must receive its fee share")]
+    LeaderboardRequired,
+    #[msg("Leaderboard prizes have already been distributed")]
+    PrizesAlreadyDistributed,
+    #[msg("A referrer can only be set on the first bet")]
+    ReferrerAlreadySet,
+    #[msg("Referrer profile does not match")]
+    InvalidReferrer,
+    #[msg("Referrer profile is required for a referred claim")]
+    ReferrerRequired,
+    #[msg("No referral rewards to claim")]
+    NoReferralRewards,
+    #[msg("Platform fee already collected for this market")]
+    FeeAlreadyCollected,
+    #[msg("Creator stake already released")]
+    StakeAlreadyReleased,
+    #[msg("Market has already been disputed")]
+    MarketAlreadyDisputed,
+    #[msg("Market was voided; claim a refund instead")]
+    MarketVoided,
+    #[msg("Market has not been voided")]
+    MarketNotVoided,
+    #[msg("Resolution deadline has not passed yet")]
+    ResolutionDeadlineNotReached,
+    #[msg("Position account passed more than once")]
+    DuplicatePosition,
+    #[msg("Bet has already been settled")]
+    BetAlreadySettled,
+    #[msg("Card mint request can still be fulfilled")]
+    MintRequestFillable,
+    #[msg("Market is still within its dispute window")]
+    DisputeWindowOpen,
+    #[msg("Market's dispute window has closed")]
+    DisputeWindowClosed,
+    #[msg("Market has an open dispute")]
+    DisputeOpen,
+    #[msg("Market has no open dispute")]
+    DisputeNotOpen,
+    #[msg("Resolution grace period must be positive")]
+    InvalidGracePeriod,
+    #[msg("Order is below the minimum size")]
+    OrderTooSmall,
+    #[msg("Too many open orders on this side of the book")]
+    TooManyOpenOrders,
+    #[msg("Position has fills waiting in the event queue")]
+    PendingFills,
+    #[msg("Leaderboard settlement period cannot be negative")]
+    InvalidSettlementPeriod,
+    #[msg("Only markets resolved by their creator can be disputed")]
+    NotCreatorResolved,
 }
@@ -0,0 +1,228 @@
+//! Fixed-point math for the logarithmic market scoring rule (LMSR) maker.
+//!
+//! All values use `FIXED_ONE` (1e12) as the unit. Share quantities and the
+//! liquidity parameter `b` are plain lamport amounts: one winning share pays
+//! out one lamport.
+//!
+//! For two outcomes the cost function is
+//! `C(q_yes, q_no) = b * ln(e^(q_yes / b) + e^(q_no / b))`, evaluated in the
+//! numerically stable form `max(q) + b * ln(1 + e^(-|q_yes - q_no| / b))`.
+
+pub const FIXED_ONE: u128 = 1_000_000_000_000;
+pub const LN_2: u128 = 693_147_180_560;
+
+// e^(-40) is below the fixed-point resolution, so larger exponents round to 0
+const MAX_EXP_INPUT: u128 = 40 * FIXED_ONE;
+const MAX_SERIES_TERMS: u32 = 64;
+
+/// e^x for a non-negative fixed-point `x` in `[0, ln 2)`, via Taylor series.
+fn exp_reduced(r: u128) -> u128 {
+    let mut sum = FIXED_ONE;
+    let mut term = FIXED_ONE;
+    for n in 1..MAX_SERIES_TERMS {
+        term = term * r / FIXED_ONE / n as u128;
+        if term == 0 {
+            break;
+        }
+        sum += term;
+    }
+    sum
+}
+
+/// e^(-x) for a non-negative fixed-point `x`.
+pub fn exp_neg(x: u128) -> u128 {
+    if x > MAX_EXP_INPUT {
+        return 0;
+    }
+    // x = k * ln 2 + r, so e^(-x) = e^(-r) / 2^k
+    let k = x / LN_2;
+    let r = x - k * LN_2;
+    let exp_neg_r = FIXED_ONE * FIXED_ONE / exp_reduced(r);
+    exp_neg_r >> k
+}
+
+/// e^x for a signed fixed-point `x`. Returns `None` on overflow.
+pub fn exp(x: i128) -> Option<u128> {
+    if x <= 0 {
+        return Some(exp_neg(x.unsigned_abs()));
+    }
+    let x = x as u128;
+    let k = x / LN_2;
+    let r = x - k * LN_2;
+    let exp_r = exp_reduced(r);
+    if k >= exp_r.leading_zeros() as u128 {
+        return None;
+    }
+    Some(exp_r << k)
+}
+
+/// Natural log of a positive fixed-point `x`. Returns `None` for zero.
+pub fn ln(x: u128) -> Option<i128> {
+    if x == 0 {
+        return None;
+    }
+    // Normalise x = y * 2^k with y in [1, 2)
+    let mut k: i128 = 0;
+    let mut y = x;
+    while y >= 2 * FIXED_ONE {
+        y >>= 1;
+        k += 1;
+    }
+    while y < FIXED_ONE {
+        y <<= 1;
+        k -= 1;
+    }
+
+    // ln(y) = 2 * atanh(z) with z = (y - 1) / (y + 1) in [0, 1/3)
+    let z = (y - FIXED_ONE) * FIXED_ONE / (y + FIXED_ONE);
+    let z_squared = z * z / FIXED_ONE;
+    let mut term = z;
+    let mut sum: u128 = 0;
+    for n in 0..MAX_SERIES_TERMS {
+        if term == 0 {
+            break;
+        }
+        sum += term / (2 * n as u128 + 1);
+        term = term * z_squared / FIXED_ONE;
+    }
+
+    Some(2 * sum as i128 + k * LN_2 as i128)
+}
+
+/// Cost function value in fixed-point lamports (scaled by `FIXED_ONE`).
+pub fn cost(yes_shares: u64, no_shares: u64, liquidity_b: u64) -> Option<u128> {
+    if liquidity_b == 0 {
+        return None;
+    }
+    let max = yes_shares.max(no_shares) as u128;
+    let diff = yes_shares.abs_diff(no_shares) as u128;
+
+    let exponent = diff.checked_mul(FIXED_ONE)? / liquidity_b as u128;
+    let log_term = ln(FIXED_ONE + exp_neg(exponent))?;
+    // ln(1 + e^-x) is never negative, but guard against series rounding
+    let log_term = log_term.max(0) as u128;
+
+    max.checked_mul(FIXED_ONE)?
+        .checked_add((liquidity_b as u128).checked_mul(log_term)?)
+}
+
+/// Instantaneous prices of YES and NO, each scaled by `FIXED_ONE`.
+///
+/// Both prices are derived from the same denominator, so they sum to
+/// `FIXED_ONE` up to one unit of rounding.
+pub fn prices(yes_shares: u64, no_shares: u64, liquidity_b: u64) -> Option<(u128, u128)> {
+    if liquidity_b == 0 {
+        return None;
+    }
+    let diff = yes_shares.abs_diff(no_shares) as u128;
+    let exponent = diff.checked_mul(FIXED_ONE)? / liquidity_b as u128;
+
+    // Normalise by the larger exponential: e^(q_lead / b) becomes 1
+    let lagging = exp_neg(exponent);
+    let denominator = FIXED_ONE + lagging;
+    let leading_price = FIXED_ONE * FIXED_ONE / denominator;
+    let lagging_price = lagging * FIXED_ONE / denominator;
+
+    if yes_shares >= no_shares {
+        Some((leading_price, lagging_price))
+    } else {
+        Some((lagging_price, leading_price))
+    }
+}
+
+/// Creator subsidy needed to cover the maker's worst-case loss, `b * ln(2)`.
+pub fn subsidy(liquidity_b: u64) -> Option<u64> {
+    to_lamports_ceil(cost(0, 0, liquidity_b)?)
+}
+
+/// Lamports a trader pays to buy `shares` of one outcome, rounded up.
+pub fn buy_cost(yes_shares: u64, no_shares: u64, liquidity_b: u64, outcome: bool, shares: u64) -> Option<u64> {
+    let before = cost(yes_shares, no_shares, liquidity_b)?;
+    let after = if outcome {
+        cost(yes_shares.checked_add(shares)?, no_shares, liquidity_b)?
+    } else {
+        cost(yes_shares, no_shares.checked_add(shares)?, liquidity_b)?
+    };
+    to_lamports_ceil(after.checked_sub(before)?)
+}
+
+/// Lamports a trader receives for selling `shares` of one outcome, rounded down.
+pub fn sell_proceeds(yes_shares: u64, no_shares: u64, liquidity_b: u64, outcome: bool, shares: u64) -> Option<u64> {
+    let before = cost(yes_shares, no_shares, liquidity_b)?;
+    let after = if outcome {
+        cost(yes_shares.checked_sub(shares)?, no_shares, liquidity_b)?
+    } else {
+        cost(yes_shares, no_shares.checked_sub(shares)?, liquidity_b)?
+    };
+    u64::try_from(before.checked_sub(after)? / FIXED_ONE).ok()
+}
+
+fn to_lamports_ceil(value: u128) -> Option<u64> {
+    u64::try_from(value.div_ceil(FIXED_ONE)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn to_f64(x: u128) -> f64 {
+        x as f64 / FIXED_ONE as f64
+    }
+
+    #[test]
+    fn exp_and_ln_match_floating_point() {
+        for i in -200..=200 {
+            let x = i as f64 / 10.0;
+            let fixed = exp((x * FIXED_ONE as f64) as i128).unwrap();
+            let expected = x.exp();
+            assert!((to_f64(fixed) - expected).abs() <= expected * 1e-9 + 1e-11, "exp({x})");
+        }
+        for i in 1..=1000 {
+            let x = i as f64 / 37.0;
+            let fixed = ln((x * FIXED_ONE as f64) as u128).unwrap();
+            assert!((fixed as f64 / FIXED_ONE as f64 - x.ln()).abs() < 1e-9, "ln({x})");
+        }
+        assert_eq!(ln(FIXED_ONE), Some(0));
+        assert_eq!(ln(0), None);
+    }
+
+    #[test]
+    fn subsidy_is_b_ln_2() {
+        assert_eq!(subsidy(1_000_000_000), Some(693_147_181));
+        assert_eq!(prices(0, 0, 1_000), Some((FIXED_ONE / 2, FIXED_ONE / 2)));
+    }
+
+    proptest! {
+        #[test]
+        fn prices_sum_to_one(yes in 0u64..1u64 << 60, no in 0u64..1u64 << 60, b in 1u64..1u64 << 50) {
+            let (yes_price, no_price) = prices(yes, no, b).unwrap();
+            let sum = yes_price + no_price;
+            prop_assert!(sum <= FIXED_ONE && FIXED_ONE - sum <= 1);
+        }
+
+        #[test]
+        fn buying_raises_price(yes in 0u64..1u64 << 40, no in 0u64..1u64 << 40, b in 1_000u64..1u64 << 40, shares in 1u64..1u64 << 40) {
+            let (before, _) = prices(yes, no, b).unwrap();
+            let (after, _) = prices(yes + shares, no, b).unwrap();
+            prop_assert!(after >= before);
+        }
+
+        #[test]
+        fn round_trip_never_profits(yes in 0u64..1u64 << 40, no in 0u64..1u64 << 40, b in 1u64..1u64 << 40, shares in 1u64..1u64 << 40, outcome: bool) {
+            let paid = buy_cost(yes, no, b, outcome, shares).unwrap();
+            let (yes_after, no_after) = if outcome { (yes + shares, no) } else { (yes, no + shares) };
+            let received = sell_proceeds(yes_after, no_after, b, outcome, shares).unwrap();
+            prop_assert!(received <= paid);
+        }
+
+        #[test]
+        fn maker_loss_bounded_by_subsidy(yes in 0u64..1u64 << 40, no in 0u64..1u64 << 40, b in 1u64..1u64 << 40) {
+            // Subsidy plus everything collected always covers the winning side
+            let collected = buy_cost(0, 0, b, true, yes).unwrap() as u128
+                + buy_cost(yes, 0, b, false, no).unwrap() as u128;
+            let reserves = subsidy(b).unwrap() as u128 + collected;
+            prop_assert!(reserves >= yes.max(no) as u128);
+        }
+    }
+}
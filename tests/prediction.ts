@@ -1,8 +1,8 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
 import { Prediction } from "../target/types/prediction";
-import { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
-import { TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID, getAssociatedTokenAddress } from "@solana/spl-token";
+import { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID, getAssociatedTokenAddress, getAccount, getMint, getOrCreateAssociatedTokenAccount, transfer } from "@solana/spl-token";
 import { assert, expect } from "chai";
 
 describe("prediction", () => {
@@ -20,6 +20,135 @@ describe("prediction", () => {
   let bettor2: Keypair;
   let bettor3: Keypair;
 
+  // Card season opened by the card config test
+  const [seasonPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("season"), new BN(1).toArrayLike(Buffer, "le", 4)],
+    program.programId
+  );
+  const [leaderboardPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("leaderboard"), new BN(1).toArrayLike(Buffer, "le", 4)],
+    program.programId
+  );
+  const [creatorProfilePda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("creator_profile"), authority.publicKey.toBuffer()],
+    program.programId
+  );
+
+  const [cardConfigPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("card_config")],
+    program.programId
+  );
+
+  const TOKEN_METADATA_PROGRAM_ID = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+  // Metaplex accounts created alongside every card mint
+  const cardMetadataAccounts = (mint: PublicKey) => ({
+    metadata: PublicKey.findProgramAddressSync(
+      [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+      TOKEN_METADATA_PROGRAM_ID
+    )[0],
+    masterEdition: PublicKey.findProgramAddressSync(
+      [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer(), Buffer.from("edition")],
+      TOKEN_METADATA_PROGRAM_ID
+    )[0],
+    cardAuthority: PublicKey.findProgramAddressSync(
+      [Buffer.from("card_authority")],
+      program.programId
+    )[0],
+    tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+    rent: SYSVAR_RENT_PUBKEY,
+  });
+
+  // Mints a current-season card straight to `owner` through the configured minter
+  const mintCard = async (owner: Keypair, power: number, rarity: number, multiplier: number) => {
+    const mintKeypair = Keypair.generate();
+    const [card] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card"), mintKeypair.publicKey.toBuffer()],
+      program.programId
+    );
+    const tokenAccount = await getAssociatedTokenAddress(mintKeypair.publicKey, owner.publicKey);
+
+    await program.methods
+      .mintCard(power, rarity, new BN(multiplier))
+      .accounts({
+        cardConfig: cardConfigPda,
+        season: seasonPda,
+        card,
+        mint: mintKeypair.publicKey,
+        tokenAccount,
+        payer: authority.publicKey,
+        owner: owner.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        ...cardMetadataAccounts(mintKeypair.publicKey),
+      })
+      .signers([mintKeypair, owner])
+      .rpc();
+
+    return { mint: mintKeypair.publicKey, card, tokenAccount };
+  };
+
+  // Position mint for one side of a market, plus the bettor's token account for it
+  const positionAccounts = async (market: PublicKey, prediction: boolean, bettor: PublicKey) => {
+    const [positionMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from(prediction ? "yes_mint" : "no_mint"), market.toBuffer()],
+      program.programId
+    );
+    return {
+      positionMint,
+      positionTokenAccount: await getAssociatedTokenAddress(positionMint, bettor),
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    };
+  };
+
+  // Opens a manually resolved market ending `duration` seconds from now
+  const createMarket = async (marketQuestion: string, duration: number) => {
+    const platform = await program.account.platform.fetch(platformPda);
+    const [market] = PublicKey.findProgramAddressSync(
+      [Buffer.from("market"), platform.totalMarkets.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [vault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), market.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .createMarket(
+        marketQuestion,
+        "",
+        new BN(Math.floor(Date.now() / 1000) + duration),
+        { crypto: {} },
+        { manual: {} },
+        { none: {} },
+        null, null, null, null, null, null, null, null, null, null
+      )
+      .accounts({
+        platform: platformPda,
+        market,
+        vault,
+        creatorProfile: creatorProfilePda,
+        authority: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return { market, vault };
+  };
+
+  // Anchor events emitted by a confirmed transaction
+  const eventParser = new anchor.EventParser(program.programId, program.coder);
+  const txEvents = async (signature: string) => {
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    return [...eventParser.parseLogs(tx.meta.logMessages)];
+  };
+
   const question = "Will BTC reach $100k by EOY 2025?";
   const description = "A prediction market for Bitcoin price reaching $100,000 USD by December 31, 2025.";
 
@@ -67,14 +196,13 @@ describe("prediction", () => {
   });
 
   it("Creates a prediction market", async () => {
-    // Set end time to 2 seconds from now for testing
-    const endTime = new BN(Math.floor(Date.now() / 1000) + 2);
-
-    // Use the first 32 bytes (or less) of the question for the seed
-    const questionSeed = question.slice(0, Math.min(question.length, 32));
+    // Set end time to 8 seconds from now for testing
+    const endTime = new BN(Math.floor(Date.now() / 1000) + 8);
 
+    // Markets are keyed by the platform's running market count
+    const platform = await program.account.platform.fetch(platformPda);
     [marketPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("market"), authority.publicKey.toBuffer(), Buffer.from(questionSeed)],
+      [Buffer.from("market"), platform.totalMarkets.toArrayLike(Buffer, "le", 8)],
       program.programId
     );
 
@@ -83,7 +211,10 @@ describe("prediction", () => {
         question,
         description,
         endTime,
-        { crypto: {} }  // MarketCategory::Crypto (removed MarketType parameter)
+        { crypto: {} },
+        { manual: {} },
+        { none: {} },
+        null, null, null, null, null, null, null, null, null, null
       )
       .accounts({
         market: marketPda,
@@ -101,6 +232,14 @@ describe("prediction", () => {
     assert.equal(market.totalYesAmount.toNumber(), 0);
     assert.equal(market.totalNoAmount.toNumber(), 0);
 
+    // No stake base or bond is configured, so the creator locks nothing
+    const creatorProfile = await program.account.creatorProfile.fetch(creatorProfilePda);
+    assert.equal(creatorProfile.marketsCreated, 1);
+    assert.equal(market.creatorStake.toNumber(), 0);
+    assert.equal(market.index.toNumber(), platform.totalMarkets.toNumber());
+    assert.equal(market.creatorBond.toNumber(), 0);
+    assert.equal(market.resolutionDeadline.toNumber(), endTime.toNumber() + 86_400);
+
     console.log("✅ Market created:", market.question);
   });
 
@@ -121,9 +260,10 @@ describe("prediction", () => {
       .placeBet(betAmount, true)
       .accounts({
         market: marketPda,
-        platform: platformPda,
         bet: bet1Pda,
+        referrerProfile: null,
         vault: vaultPda,
+        ...(await positionAccounts(marketPda, true, bettor1.publicKey)),
         bettor: bettor1.publicKey,
         systemProgram: SystemProgram.programId,
       })
@@ -131,9 +271,18 @@ describe("prediction", () => {
       .rpc();
 
     const bet = await program.account.bet.fetch(bet1Pda);
-    assert.equal(bet.amount.toNumber(), betAmount.toNumber());
-    assert.equal(bet.prediction, true);
-    assert.equal(bet.claimed, false);
+    assert.equal(bet.yesAmount.toNumber(), betAmount.toNumber());
+    assert.equal(bet.noAmount.toNumber(), 0);
+
+    const [yesMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("yes_mint"), marketPda.toBuffer()],
+      program.programId
+    );
+    const position = await getAccount(
+      provider.connection,
+      await getAssociatedTokenAddress(yesMint, bettor1.publicKey)
+    );
+    assert.equal(Number(position.amount), betAmount.toNumber());
 
     const market = await program.account.market.fetch(marketPda);
     assert.equal(market.totalYesAmount.toNumber(), betAmount.toNumber());
@@ -155,7 +304,9 @@ describe("prediction", () => {
         market: marketPda,
         platform: platformPda,
         bet: bet2Pda,
+        referrerProfile: null,
         vault: vaultPda,
+        ...(await positionAccounts(marketPda, false, bettor2.publicKey)),
         bettor: bettor2.publicKey,
         systemProgram: SystemProgram.programId,
       })
@@ -163,8 +314,8 @@ describe("prediction", () => {
       .rpc();
 
     const bet = await program.account.bet.fetch(bet2Pda);
-    assert.equal(bet.amount.toNumber(), betAmount.toNumber());
-    assert.equal(bet.prediction, false);
+    assert.equal(bet.noAmount.toNumber(), betAmount.toNumber());
+    assert.equal(bet.yesAmount.toNumber(), 0);
 
     const market = await program.account.market.fetch(marketPda);
     assert.equal(market.totalNoAmount.toNumber(), betAmount.toNumber());
@@ -186,7 +337,9 @@ describe("prediction", () => {
         market: marketPda,
         platform: platformPda,
         bet: bet3Pda,
+        referrerProfile: null,
         vault: vaultPda,
+        ...(await positionAccounts(marketPda, true, bettor3.publicKey)),
         bettor: bettor3.publicKey,
         systemProgram: SystemProgram.programId,
       })
@@ -201,12 +354,125 @@ describe("prediction", () => {
     console.log(`   Total pool: ${(market.totalYesAmount.toNumber() + market.totalNoAmount.toNumber()) / LAMPORTS_PER_SOL} SOL`);
   });
 
+  it("Keeps hedged YES and NO stakes separate", async () => {
+    const [bet3Pda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), marketPda.toBuffer(), bettor3.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const hedgeAmount = new BN(0.5 * LAMPORTS_PER_SOL);
+
+    await program.methods
+      .placeBet(hedgeAmount, false)
+      .accounts({
+        market: marketPda,
+        platform: platformPda,
+        bet: bet3Pda,
+        referrerProfile: null,
+        vault: vaultPda,
+        ...(await positionAccounts(marketPda, false, bettor3.publicKey)),
+        bettor: bettor3.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor3])
+      .rpc();
+
+    // The earlier YES stake must not be converted to NO
+    const bet = await program.account.bet.fetch(bet3Pda);
+    assert.equal(bet.yesAmount.toNumber(), 1.5 * LAMPORTS_PER_SOL);
+    assert.equal(bet.noAmount.toNumber(), hedgeAmount.toNumber());
+
+    const market = await program.account.market.fetch(marketPda);
+    assert.equal(market.totalYesAmount.toNumber(), 2.5 * LAMPORTS_PER_SOL);
+    assert.equal(market.totalNoAmount.toNumber(), 2.5 * LAMPORTS_PER_SOL);
+
+    console.log("✅ Bettor3 hedged 0.5 SOL on NO");
+  });
+
+  it("Withdraws a stake early and forfeits the penalty to the pool", async () => {
+    const { market, vault } = await createMarket("Early exit test", 60);
+    const [betPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), market.toBuffer(), bettor2.publicKey.toBuffer()],
+      program.programId
+    );
+    const positions = await positionAccounts(market, true, bettor2.publicKey);
+    const stake = new BN(1 * LAMPORTS_PER_SOL);
+    const withdrawn = new BN(0.4 * LAMPORTS_PER_SOL);
+
+    await program.methods
+      .placeBet(stake, true)
+      .accounts({
+        market,
+        platform: platformPda,
+        bet: betPda,
+        referrerProfile: null,
+        vault,
+        ...positions,
+        bettor: bettor2.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor2])
+      .rpc();
+
+    const balanceBefore = await provider.connection.getBalance(bettor2.publicKey);
+    await program.methods
+      .withdrawBet(withdrawn, true)
+      .accounts({
+        market,
+        platform: platformPda,
+        bet: betPda,
+        vault,
+        positionMint: positions.positionMint,
+        positionTokenAccount: positions.positionTokenAccount,
+        bettor: bettor2.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor2])
+      .rpc();
+    const balanceAfter = await provider.connection.getBalance(bettor2.publicKey);
+
+    const platform = await program.account.platform.fetch(platformPda);
+    const penalty = withdrawn.toNumber() * platform.earlyExitPenaltyBps / 10_000;
+    assert.equal(balanceAfter - balanceBefore, withdrawn.toNumber() - penalty);
+
+    // The withdrawn position tokens are burned and the penalty stays in the pool
+    const position = await getAccount(provider.connection, positions.positionTokenAccount);
+    assert.equal(Number(position.amount), stake.sub(withdrawn).toNumber());
+    const bet = await program.account.bet.fetch(betPda);
+    assert.equal(bet.yesAmount.toNumber(), stake.sub(withdrawn).toNumber());
+    const marketAccount = await program.account.market.fetch(market);
+    assert.equal(marketAccount.totalYesAmount.toNumber(), stake.sub(withdrawn).toNumber());
+    assert.equal(marketAccount.forfeitedAmount.toNumber(), penalty);
+
+    console.log(`✅ Bettor2 withdrew early and forfeited ${penalty / LAMPORTS_PER_SOL} SOL`);
+  });
+
+  it("Cannot expire a market before its resolution deadline", async () => {
+    try {
+      await program.methods
+        .expireMarket()
+        .accounts({
+          market: marketPda,
+          creatorProfile: creatorProfilePda,
+          vault: vaultPda,
+        })
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("ResolutionDeadlineNotReached");
+      console.log("✅ Market cannot be voided before the deadline");
+    }
+  });
+
   it("Fails to resolve market before end time", async () => {
     try {
       await program.methods
         .resolveMarket(true)
         .accounts({
           market: marketPda,
+          creatorProfile: creatorProfilePda,
+          vault: vaultPda,
           authority: authority.publicKey,
         })
         .rpc();
@@ -218,14 +484,16 @@ describe("prediction", () => {
   });
 
   it("Waits for market end time and resolves market", async () => {
-    // Wait for market to end (2+ seconds)
+    // Wait for market to end (8+ seconds after creation)
     console.log("   ⏳ Waiting for market end time...");
-    await new Promise(resolve => setTimeout(resolve, 3000));
+    await new Promise(resolve => setTimeout(resolve, 9000));
 
     await program.methods
       .resolveMarket(true) // YES wins
       .accounts({
         market: marketPda,
+        creatorProfile: creatorProfilePda,
+        vault: vaultPda,
         authority: authority.publicKey,
       })
       .rpc();
@@ -238,10 +506,11 @@ describe("prediction", () => {
   });
 
   it("Winners claim their winnings", async () => {
-    const [bet1Pda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("bet"), marketPda.toBuffer(), bettor1.publicKey.toBuffer()],
+    const [yesMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("yes_mint"), marketPda.toBuffer()],
       program.programId
     );
+    const position1 = await getAssociatedTokenAddress(yesMint, bettor1.publicKey);
 
     const bettor1BalanceBefore = await provider.connection.getBalance(bettor1.publicKey);
 
@@ -249,9 +518,15 @@ describe("prediction", () => {
       .claimWinnings()
       .accounts({
         market: marketPda,
-        bet: bet1Pda,
+        platform: platformPda,
         vault: vaultPda,
+        bet: null,
+        card: null,
+        referrerProfile: null,
+        positionMint: yesMint,
+        positionTokenAccount: position1,
         bettor: bettor1.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .signers([bettor1])
@@ -260,24 +535,50 @@ describe("prediction", () => {
     const bettor1BalanceAfter = await provider.connection.getBalance(bettor1.publicKey);
     const winnings = bettor1BalanceAfter - bettor1BalanceBefore;
 
-    const bet = await program.account.bet.fetch(bet1Pda);
-    assert.equal(bet.claimed, true);
-
-    console.log(`✅ Bettor1 claimed ${winnings / LAMPORTS_PER_SOL} SOL`);
+    const position = await getAccount(provider.connection, position1);
+    assert.equal(Number(position.amount), 0);
 
-    // Bettor3 also wins
-    const [bet3Pda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("bet"), marketPda.toBuffer(), bettor3.publicKey.toBuffer()],
+    // Profile stats move when the bet settles, which anyone can trigger
+    const [profilePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_profile"), bettor1.publicKey.toBuffer()],
+      program.programId
+    );
+    const [bet1Pda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), marketPda.toBuffer(), bettor1.publicKey.toBuffer()],
       program.programId
     );
+    await program.methods
+      .settleBet()
+      .accounts({
+        market: marketPda,
+        bet: bet1Pda,
+        card: null,
+        userProfile: profilePda,
+      })
+      .rpc();
+    const profile = await program.account.userProfile.fetch(profilePda);
+    assert.equal(profile.marketsEntered, 1);
+    assert.equal(profile.betsWon, 1);
+    assert.equal(profile.winStreak, 1);
+    assert.isAbove(profile.totalWon.toNumber(), 0);
+    assert.isAbove(profile.realizedPnl.toNumber(), 0);
 
+    console.log(`✅ Bettor1 claimed ${winnings / LAMPORTS_PER_SOL} SOL`);
+
+    // Bettor3 also wins
     await program.methods
       .claimWinnings()
       .accounts({
         market: marketPda,
-        bet: bet3Pda,
+        platform: platformPda,
         vault: vaultPda,
+        bet: null,
+        card: null,
+        referrerProfile: null,
+        positionMint: yesMint,
+        positionTokenAccount: await getAssociatedTokenAddress(yesMint, bettor3.publicKey),
         bettor: bettor3.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .signers([bettor3])
@@ -287,8 +588,8 @@ describe("prediction", () => {
   });
 
   it("Losers cannot claim", async () => {
-    const [bet2Pda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("bet"), marketPda.toBuffer(), bettor2.publicKey.toBuffer()],
+    const [noMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("no_mint"), marketPda.toBuffer()],
       program.programId
     );
 
@@ -297,9 +598,15 @@ describe("prediction", () => {
         .claimWinnings()
         .accounts({
           market: marketPda,
-          bet: bet2Pda,
+          platform: platformPda,
           vault: vaultPda,
+          bet: null,
+          card: null,
+          referrerProfile: null,
+          positionMint: noMint,
+          positionTokenAccount: await getAssociatedTokenAddress(noMint, bettor2.publicKey),
           bettor: bettor2.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .signers([bettor2])
@@ -309,11 +616,38 @@ describe("prediction", () => {
       expect(err.toString()).to.include("LosingBet");
       console.log("✅ Correctly prevented loser from claiming");
     }
+
+    // The loss is realised without the loser's cooperation
+    const [profilePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_profile"), bettor2.publicKey.toBuffer()],
+      program.programId
+    );
+    const [bet2Pda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), marketPda.toBuffer(), bettor2.publicKey.toBuffer()],
+      program.programId
+    );
+    const profileBefore = await program.account.userProfile.fetch(profilePda);
+    await program.methods
+      .settleBet()
+      .accounts({
+        market: marketPda,
+        bet: bet2Pda,
+        card: null,
+        userProfile: profilePda,
+      })
+      .rpc();
+    const profile = await program.account.userProfile.fetch(profilePda);
+    assert.equal(profile.betsLost, profileBefore.betsLost + 1);
+    assert.equal(profile.winStreak, 0);
+    assert.equal(
+      profile.realizedPnl.toNumber(),
+      profileBefore.realizedPnl.toNumber() - 2 * LAMPORTS_PER_SOL
+    );
   });
 
   it("Prevents double claiming", async () => {
-    const [bet1Pda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("bet"), marketPda.toBuffer(), bettor1.publicKey.toBuffer()],
+    const [yesMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("yes_mint"), marketPda.toBuffer()],
       program.programId
     );
 
@@ -322,61 +656,1807 @@ describe("prediction", () => {
         .claimWinnings()
         .accounts({
           market: marketPda,
-          bet: bet1Pda,
+          platform: platformPda,
           vault: vaultPda,
+          bet: null,
+          card: null,
+          referrerProfile: null,
+          positionMint: yesMint,
+          positionTokenAccount: await getAssociatedTokenAddress(yesMint, bettor1.publicKey),
           bettor: bettor1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .signers([bettor1])
         .rpc();
       assert.fail("Should have thrown error");
     } catch (err) {
-      expect(err.toString()).to.include("AlreadyClaimed");
+      expect(err.toString()).to.include("EmptyPosition");
       console.log("✅ Correctly prevented double claiming");
     }
   });
 
-  it("Registers a Card (mint_card)", async () => {
-    // Create mint keypair for the NFT
-    const mintKeypair = Keypair.generate();
-
-    const [cardPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("card"), mintKeypair.publicKey.toBuffer()],
+  it("Fills crossing book orders and pays the winning side", async () => {
+    const { market, vault } = await createMarket("Order book fill test", 4);
+    const [orderBook] = PublicKey.findProgramAddressSync(
+      [Buffer.from("order_book"), market.toBuffer()],
       program.programId
     );
-
-    // Derive the associated token account for the owner
-    const tokenAccount = await getAssociatedTokenAddress(
-      mintKeypair.publicKey,
-      bettor1.publicKey
+    const [eventQueue] = PublicKey.findProgramAddressSync(
+      [Buffer.from("event_queue"), market.toBuffer()],
+      program.programId
     );
+    const bookPosition = (owner: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from("book_position"), market.toBuffer(), owner.toBuffer()],
+      program.programId
+    )[0];
+    const bidPosition = bookPosition(bettor1.publicKey);
+    const askPosition = bookPosition(bettor2.publicKey);
 
-    const multiplier = new BN(1500);
-
-    // Both payer (authority) and mint keypair must sign
     await program.methods
-      .mintCard(5, 2, multiplier)
+      .initializeOrderBook()
       .accounts({
-        card: cardPda,
-        mint: mintKeypair.publicKey,
-        tokenAccount: tokenAccount,
+        market,
+        orderBook,
+        eventQueue,
         payer: authority.publicKey,
-        owner: bettor1.publicKey,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
-      .signers([mintKeypair, bettor1])  // Both mint and owner must sign
       .rpc();
 
-    const card = await program.account.card.fetch(cardPda);
-    assert.equal(card.mint.toBase58(), mintKeypair.publicKey.toBase58());
-    assert.equal(card.owner.toBase58(), bettor1.publicKey.toBase58());
-    assert.equal(card.power, 5);
-    assert.equal(card.rarity, 2);
-    assert.equal(card.multiplier.toNumber(), 1500);
-    assert.equal(card.wins.toNumber(), 0);
+    // Dust orders would crowd out real liquidity
+    try {
+      await program.methods
+        .placeOrder({ bid: {} }, new BN(6_000), new BN(1))
+        .accounts({
+          market,
+          orderBook,
+          position: bidPosition,
+          vault,
+          owner: bettor1.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bettor1])
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("OrderTooSmall");
+    }
 
-    console.log("✅ Card registered and metadata stored");
+    // 100 shares crossing at 0.6: the bid escrows 600_000, the ask 400_000
+    for (const [bettor, side, position] of [
+      [bettor1, { bid: {} }, bidPosition],
+      [bettor2, { ask: {} }, askPosition],
+    ] as const) {
+      await program.methods
+        .placeOrder(side, new BN(6_000), new BN(100))
+        .accounts({
+          market,
+          orderBook,
+          position,
+          vault,
+          owner: bettor.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bettor])
+        .rpc();
+    }
+
+    const matchTx = await program.methods
+      .matchOrders(4)
+      .accounts({ market, platform: platformPda, orderBook, eventQueue })
+      .rpc({ commitment: "confirmed" });
+
+    // The ask arrived second, so it is the taker
+    const fill = (await txEvents(matchTx)).find((event) => event.name === "orderFilled");
+    assert.equal(fill.data.price.toNumber(), 6_000);
+    assert.equal(fill.data.quantity.toNumber(), 100);
+    assert.property(fill.data.takerSide, "ask");
+
+    // Passing the same position twice would let its stale copy overwrite the fill
+    try {
+      await program.methods
+        .consumeEvents(4)
+        .accounts({ market, eventQueue })
+        .remainingAccounts([
+          { pubkey: bidPosition, isWritable: true, isSigner: false },
+          { pubkey: bidPosition, isWritable: true, isSigner: false },
+        ])
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("DuplicatePosition");
+    }
+
+    await new Promise(resolve => setTimeout(resolve, 5000));
+    await program.methods
+      .resolveMarket(true)
+      .accounts({
+        market,
+        creatorProfile: creatorProfilePda,
+        vault,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    const claimAccounts = {
+      market,
+      eventQueue,
+      position: bidPosition,
+      vault,
+      owner: bettor1.publicKey,
+      systemProgram: SystemProgram.programId,
+    };
+
+    // The fill is still queued, so claiming now would forfeit its shares
+    try {
+      await program.methods
+        .claimBookWinnings()
+        .accounts(claimAccounts)
+        .signers([bettor1])
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("PendingFills");
+    }
+
+    await program.methods
+      .consumeEvents(4)
+      .accounts({ market, eventQueue })
+      .remainingAccounts([
+        { pubkey: bidPosition, isWritable: true, isSigner: false },
+        { pubkey: askPosition, isWritable: true, isSigner: false },
+      ])
+      .rpc();
+
+    assert.equal((await program.account.bookPosition.fetch(bidPosition)).yesShares.toNumber(), 100);
+    assert.equal((await program.account.bookPosition.fetch(askPosition)).noShares.toNumber(), 100);
+
+    const balanceBefore = await provider.connection.getBalance(bettor1.publicKey);
+    await program.methods
+      .claimBookWinnings()
+      .accounts(claimAccounts)
+      .signers([bettor1])
+      .rpc();
+    const balanceAfter = await provider.connection.getBalance(bettor1.publicKey);
+
+    // Each winning share pays the full 10_000 lamport set value
+    assert.equal(balanceAfter - balanceBefore, 1_000_000);
+    assert.isTrue((await program.account.bookPosition.fetch(bidPosition)).claimed);
+
+    console.log("✅ Book fill settled and the YES side claimed");
+  });
+
+  it("Configures card rarity tiers", async () => {
+    const [cardConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card_config")],
+      program.programId
+    );
+
+    const tier = (weight: number, maxActiveBattles: number, minPower: number, maxPower: number, minMultiplier: number, maxMultiplier: number) => ({
+      weight,
+      maxActiveBattles,
+      minPower,
+      maxPower,
+      minMultiplier: new BN(minMultiplier),
+      maxMultiplier: new BN(maxMultiplier),
+    });
+
+    await program.methods
+      // The provider wallet doubles as the local test randomness oracle
+      .initializeCardConfig(authority.publicKey, authority.publicKey, new BN(0), [
+        tier(6000, 5, 1, 3, 1000, 1100),   // Common
+        tier(2500, 4, 2, 5, 1100, 1300),   // Uncommon
+        tier(1000, 3, 4, 7, 1300, 1600),   // Rare
+        tier(400, 2, 6, 9, 1600, 2000),    // Epic
+        tier(100, 1, 8, 10, 2000, 3000),   // Legendary
+      ], "https://cards.example/{rarity}.json", new BN(3600))
+      .accounts({
+        platform: platformPda,
+        cardConfig: cardConfigPda,
+        authority: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    // Cards can only be minted during a season
+    await program.methods
+      .startSeason(1, [10_000, 1_000, 100, 10, 1], new BN(Math.floor(Date.now() / 1000) + 86_400))
+      .accounts({
+        platform: platformPda,
+        cardConfig: cardConfigPda,
+        season: seasonPda,
+        leaderboard: leaderboardPda,
+        authority: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const leaderboard = await program.account.leaderboard.fetch(leaderboardPda);
+    assert.equal(leaderboard.seasonId, 1);
+    assert.equal(leaderboard.entries.length, 0);
+
+    const cardConfig = await program.account.cardConfig.fetch(cardConfigPda);
+    assert.equal(cardConfig.tiers.length, 5);
+    assert.equal(cardConfig.minter.toBase58(), authority.publicKey.toBase58());
+    assert.equal(cardConfig.currentSeason, 1);
+
+    console.log("✅ Card config initialized");
+  });
+
+  it("Rejects card stats outside the rarity tier", async () => {
+    const mintKeypair = Keypair.generate();
+
+    const [cardPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card"), mintKeypair.publicKey.toBuffer()],
+      program.programId
+    );
+    const [cardConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card_config")],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .mintCard(10, 0, new BN(1_000_000))
+        .accounts({
+          cardConfig: cardConfigPda,
+          season: seasonPda,
+          card: cardPda,
+          mint: mintKeypair.publicKey,
+          tokenAccount: await getAssociatedTokenAddress(mintKeypair.publicKey, bettor1.publicKey),
+          payer: authority.publicKey,
+          owner: bettor1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          ...cardMetadataAccounts(mintKeypair.publicKey),
+        })
+        .signers([mintKeypair, bettor1])
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("CardStatsOutOfRange");
+      console.log("✅ Correctly rejected a 1000x common card");
+    }
+  });
+
+  it("Registers a Card (mint_card)", async () => {
+    // Create mint keypair for the NFT
+    const mintKeypair = Keypair.generate();
+
+    const [cardConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card_config")],
+      program.programId
+    );
+
+    const [cardPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card"), mintKeypair.publicKey.toBuffer()],
+      program.programId
+    );
+
+    // Derive the associated token account for the owner
+    const tokenAccount = await getAssociatedTokenAddress(
+      mintKeypair.publicKey,
+      bettor1.publicKey
+    );
+
+    const multiplier = new BN(1500);
+
+    // Both payer (authority) and mint keypair must sign
+    await program.methods
+      .mintCard(5, 2, multiplier)
+      .accounts({
+        cardConfig: cardConfigPda,
+        season: seasonPda,
+        card: cardPda,
+        mint: mintKeypair.publicKey,
+        tokenAccount: tokenAccount,
+        payer: authority.publicKey,
+        owner: bettor1.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        ...cardMetadataAccounts(mintKeypair.publicKey),
+      })
+      .signers([mintKeypair, bettor1])  // Both mint and owner must sign
+      .rpc();
+
+    const card = await program.account.card.fetch(cardPda);
+    assert.equal(card.mint.toBase58(), mintKeypair.publicKey.toBase58());
+    assert.equal(card.owner.toBase58(), bettor1.publicKey.toBase58());
+    assert.equal(card.power, 5);
+    assert.equal(card.rarity, 2);
+    assert.equal(card.multiplier.toNumber(), 1500);
+    assert.equal(card.wins.toNumber(), 0);
+    assert.equal(card.seasonId, 1);
+
+    const season = await program.account.season.fetch(seasonPda);
+    assert.equal(season.minted[2], 1);
+
+    // The master edition owns the mint authority, so no second token can exist
+    const mintInfo = await getMint(provider.connection, mintKeypair.publicKey);
+    assert.equal(Number(mintInfo.supply), 1);
+    assert.equal(mintInfo.mintAuthority.toBase58(), cardMetadataAccounts(mintKeypair.publicKey).masterEdition.toBase58());
+
+    console.log("✅ Card registered and metadata stored");
+  });
+
+  it("Mints a card with oracle-rolled stats", async () => {
+    const [cardConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card_config")],
+      program.programId
+    );
+    const seed = Array.from(Keypair.generate().publicKey.toBytes());
+
+    const [requestPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card_mint_request"), authority.publicKey.toBuffer(), Buffer.from(seed)],
+      program.programId
+    );
+    const [randomnessPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("randomness"), requestPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .requestCardMint(seed)
+      .accounts({
+        cardConfig: cardConfigPda,
+        season: seasonPda,
+        request: requestPda,
+        randomness: randomnessPda,
+        owner: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    // The season is open with supply left, so the request cannot be refunded
+    try {
+      await program.methods
+        .cancelCardMintRequest()
+        .accounts({
+          season: seasonPda,
+          request: requestPda,
+          randomness: randomnessPda,
+          owner: authority.publicKey,
+        })
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("MintRequestFillable");
+    }
+
+    // Local test oracle: reveal random bytes through the same account interface
+    await program.methods
+      .fulfillRandomness(Array.from(Keypair.generate().secretKey.slice(0, 32)))
+      .accounts({
+        randomness: randomnessPda,
+        oracle: authority.publicKey,
+      })
+      .rpc();
+
+    // With the roll revealed, cancelling would let the owner refuse a bad card
+    try {
+      await program.methods
+        .cancelCardMintRequest()
+        .accounts({
+          season: seasonPda,
+          request: requestPda,
+          randomness: randomnessPda,
+          owner: authority.publicKey,
+        })
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("RandomnessAlreadyFulfilled");
+    }
+
+    const mintKeypair = Keypair.generate();
+    const [cardPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card"), mintKeypair.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .fulfillCardMint()
+      .accounts({
+        platform: platformPda,
+        cardConfig: cardConfigPda,
+        treasury: treasuryKeypair.publicKey,
+        season: seasonPda,
+        request: requestPda,
+        randomness: randomnessPda,
+        card: cardPda,
+        mint: mintKeypair.publicKey,
+        tokenAccount: await getAssociatedTokenAddress(mintKeypair.publicKey, authority.publicKey),
+        owner: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        ...cardMetadataAccounts(mintKeypair.publicKey),
+      })
+      .signers([mintKeypair])
+      .rpc();
+
+    const card = await program.account.card.fetch(cardPda);
+    const cardConfig = await program.account.cardConfig.fetch(cardConfigPda);
+    const tier = cardConfig.tiers[card.rarity];
+    assert.isAtLeast(card.power, tier.minPower);
+    assert.isAtMost(card.power, tier.maxPower);
+
+    console.log(`✅ Rolled a rarity ${card.rarity} card with power ${card.power}`);
+  });
+
+  it("Fuses three common cards into an uncommon card", async () => {
+    const [cardConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card_config")],
+      program.programId
+    );
+
+    // Mint three Common cards to the authority to burn
+    const burned: PublicKey[] = [];
+    for (let i = 0; i < 3; i++) {
+      const mintKeypair = Keypair.generate();
+      const [cardPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("card"), mintKeypair.publicKey.toBuffer()],
+        program.programId
+      );
+      const tokenAccount = await getAssociatedTokenAddress(mintKeypair.publicKey, authority.publicKey);
+
+      await program.methods
+        .mintCard(1 + i, 0, new BN(1000))
+        .accounts({
+          cardConfig: cardConfigPda,
+          season: seasonPda,
+          card: cardPda,
+          mint: mintKeypair.publicKey,
+          tokenAccount,
+          payer: authority.publicKey,
+          owner: authority.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          ...cardMetadataAccounts(mintKeypair.publicKey),
+        })
+        .signers([mintKeypair])
+        .rpc();
+
+      burned.push(cardPda, mintKeypair.publicKey, tokenAccount);
+    }
+
+    const seed = Array.from(Keypair.generate().publicKey.toBytes());
+    const [requestPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card_mint_request"), authority.publicKey.toBuffer(), Buffer.from(seed)],
+      program.programId
+    );
+    const [randomnessPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("randomness"), requestPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .fuseCards(seed)
+      .accounts({
+        cardConfig: cardConfigPda,
+        season: seasonPda,
+        request: requestPda,
+        randomness: randomnessPda,
+        owner: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(burned.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+      .rpc();
+
+    // Burned cards are gone before the roll is revealed
+    assert.isNull(await provider.connection.getAccountInfo(burned[0]));
+
+    await program.methods
+      .fulfillRandomness(Array.from(Keypair.generate().secretKey.slice(0, 32)))
+      .accounts({
+        randomness: randomnessPda,
+        oracle: authority.publicKey,
+      })
+      .rpc();
+
+    const mintKeypair = Keypair.generate();
+    const [cardPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card"), mintKeypair.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .fulfillCardMint()
+      .accounts({
+        platform: platformPda,
+        cardConfig: cardConfigPda,
+        treasury: treasuryKeypair.publicKey,
+        season: seasonPda,
+        request: requestPda,
+        randomness: randomnessPda,
+        card: cardPda,
+        mint: mintKeypair.publicKey,
+        tokenAccount: await getAssociatedTokenAddress(mintKeypair.publicKey, authority.publicKey),
+        owner: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        ...cardMetadataAccounts(mintKeypair.publicKey),
+      })
+      .signers([mintKeypair])
+      .rpc();
+
+    const card = await program.account.card.fetch(cardPda);
+    assert.equal(card.rarity, 1);
+    assert.isAtLeast(card.power, 3);
+
+    console.log(`✅ Fused into an uncommon card with power ${card.power}`);
+  });
+
+  it("Rejects a duel on a resolved market", async () => {
+    const [cardConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card_config")],
+      program.programId
+    );
+    const mintKeypair = Keypair.generate();
+    const [cardPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card"), mintKeypair.publicKey.toBuffer()],
+      program.programId
+    );
+    const tokenAccount = await getAssociatedTokenAddress(mintKeypair.publicKey, authority.publicKey);
+
+    await program.methods
+      .mintCard(2, 0, new BN(1000))
+      .accounts({
+        cardConfig: cardConfigPda,
+        season: seasonPda,
+        card: cardPda,
+        mint: mintKeypair.publicKey,
+        tokenAccount,
+        payer: authority.publicKey,
+        owner: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        ...cardMetadataAccounts(mintKeypair.publicKey),
+      })
+      .signers([mintKeypair])
+      .rpc();
+
+    const [duelPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("duel"), marketPda.toBuffer(), cardPda.toBuffer()],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .createDuel(new BN(0.1 * LAMPORTS_PER_SOL), true)
+        .accounts({
+          market: marketPda,
+          cardConfig: cardConfigPda,
+          card: cardPda,
+          cardTokenAccount: tokenAccount,
+          duel: duelPda,
+          challenger: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (error) {
+      expect(error.toString()).to.include("MarketAlreadyResolved");
+      console.log("✅ Duels only open on unresolved markets");
+    }
+  });
+
+  it("Lists a card for rent and delists it", async () => {
+    const [cardConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card_config")],
+      program.programId
+    );
+    const mintKeypair = Keypair.generate();
+    const [cardPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card"), mintKeypair.publicKey.toBuffer()],
+      program.programId
+    );
+    const [listingPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("card_listing"), mintKeypair.publicKey.toBuffer()],
+      program.programId
+    );
+    const lenderTokenAccount = await getAssociatedTokenAddress(mintKeypair.publicKey, authority.publicKey);
+    const escrowTokenAccount = await getAssociatedTokenAddress(mintKeypair.publicKey, listingPda, true);
+
+    await program.methods
+      .mintCard(2, 0, new BN(1000))
+      .accounts({
+        cardConfig: cardConfigPda,
+        season: seasonPda,
+        card: cardPda,
+        mint: mintKeypair.publicKey,
+        tokenAccount: lenderTokenAccount,
+        payer: authority.publicKey,
+        owner: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        ...cardMetadataAccounts(mintKeypair.publicKey),
+      })
+      .signers([mintKeypair])
+      .rpc();
+
+    await program.methods
+      .listCardForRent(new BN(0.01 * LAMPORTS_PER_SOL), new BN(3600))
+      .accounts({
+        card: cardPda,
+        mint: mintKeypair.publicKey,
+        cardListing: listingPda,
+        lenderTokenAccount,
+        escrowTokenAccount,
+        lender: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    let escrow = await getAccount(provider.connection, escrowTokenAccount);
+    assert.equal(Number(escrow.amount), 1);
+
+    // Nobody else can pull an unrented card out of escrow
+    try {
+      await program.methods
+        .reclaimCard()
+        .accounts({
+          cardListing: listingPda,
+          escrowTokenAccount,
+          lenderTokenAccount,
+          lender: authority.publicKey,
+          caller: bettor1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bettor1])
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (error) {
+      expect(error.toString()).to.include("Unauthorized");
+    }
+
+    await program.methods
+      .reclaimCard()
+      .accounts({
+        cardListing: listingPda,
+        escrowTokenAccount,
+        lenderTokenAccount,
+        lender: authority.publicKey,
+        caller: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const lenderAccount = await getAccount(provider.connection, lenderTokenAccount);
+    assert.equal(Number(lenderAccount.amount), 1);
+    assert.isNull(await provider.connection.getAccountInfo(listingPda));
+
+    console.log("✅ Card listed and returned to the lender");
+  });
+
+  it("Settles a losing battle bet without the bettor", async () => {
+    const { market, vault } = await createMarket("Battle settlement test", 4);
+    const { card, tokenAccount } = await mintCard(bettor2, 2, 0, 1050);
+    const [betPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), market.toBuffer(), bettor2.publicKey.toBuffer()],
+      program.programId
+    );
+    const [bettor2Profile] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_profile"), bettor2.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .battle(new BN(0.1 * LAMPORTS_PER_SOL), true)
+      .accounts({
+        market,
+        platform: platformPda,
+        card,
+        cardConfig: cardConfigPda,
+        cardTokenAccount: tokenAccount,
+        cardListing: null,
+        bet: betPda,
+        referrerProfile: null,
+        vault,
+        ...(await positionAccounts(market, true, bettor2.publicKey)),
+        player: bettor2.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor2])
+      .rpc();
+    assert.equal((await program.account.card.fetch(card)).activeBattles, 1);
+
+    await new Promise(resolve => setTimeout(resolve, 5000));
+    await program.methods
+      .resolveMarket(false)
+      .accounts({
+        market,
+        creatorProfile: creatorProfilePda,
+        vault,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    // The provider wallet cranks the loser's bet; bettor2 never signs
+    await program.methods
+      .settleBet()
+      .accounts({ market, bet: betPda, card, userProfile: bettor2Profile })
+      .rpc();
+
+    const cardAccount = await program.account.card.fetch(card);
+    assert.equal(cardAccount.losses.toNumber(), 1);
+    assert.equal(cardAccount.activeBattles, 0);
+
+    try {
+      await program.methods
+        .settleBet()
+        .accounts({ market, bet: betPda, card, userProfile: bettor2Profile })
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("BetAlreadySettled");
+    }
+
+    console.log("✅ Losing battle recorded and card slot freed by a third party");
+  });
+
+  it("Syncs the card owner after a token transfer", async () => {
+    const { mint, card, tokenAccount } = await mintCard(bettor1, 2, 0, 1000);
+    const buyerAccount = await getOrCreateAssociatedTokenAccount(provider.connection, authority, mint, bettor3.publicKey);
+    await transfer(provider.connection, authority, tokenAccount, buyerAccount.address, bettor1, 1);
+
+    // The seller's emptied account can no longer claim the card
+    try {
+      await program.methods
+        .syncCardOwner()
+        .accounts({ card, cardTokenAccount: tokenAccount })
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("NotCardOwner");
+    }
+
+    await program.methods
+      .syncCardOwner()
+      .accounts({ card, cardTokenAccount: buyerAccount.address })
+      .rpc();
+
+    const cardAccount = await program.account.card.fetch(card);
+    assert.equal(cardAccount.owner.toBase58(), bettor3.publicKey.toBase58());
+
+    console.log("✅ Card owner follows the token holder");
+  });
+
+  it("Earns XP from a winning battle and levels the card up", async () => {
+    const { market, vault } = await createMarket("Battle XP test", 4);
+    const { card, tokenAccount } = await mintCard(bettor3, 2, 0, 1000);
+    const [betPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), market.toBuffer(), bettor3.publicKey.toBuffer()],
+      program.programId
+    );
+    const [bettor3Profile] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_profile"), bettor3.publicKey.toBuffer()],
+      program.programId
+    );
+
+    // The card's stake is the whole winning pool, so XP is its net stake at even odds
+    await program.methods
+      .battle(new BN(1 * LAMPORTS_PER_SOL), true)
+      .accounts({
+        market,
+        platform: platformPda,
+        card,
+        cardConfig: cardConfigPda,
+        cardTokenAccount: tokenAccount,
+        cardListing: null,
+        bet: betPda,
+        referrerProfile: null,
+        vault,
+        ...(await positionAccounts(market, true, bettor3.publicKey)),
+        player: bettor3.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor3])
+      .rpc();
+
+    await new Promise(resolve => setTimeout(resolve, 5000));
+    await program.methods
+      .resolveMarket(true)
+      .accounts({
+        market,
+        creatorProfile: creatorProfilePda,
+        vault,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .settleBet()
+      .accounts({ market, bet: betPda, card, userProfile: bettor3Profile })
+      .rpc();
+
+    let cardAccount = await program.account.card.fetch(card);
+    assert.equal(cardAccount.wins.toNumber(), 1);
+    assert.equal(cardAccount.experience.toNumber(), 100);
+
+    // The default curve needs 100 XP for level 1
+    const levelUpTx = await program.methods
+      .levelUp()
+      .accounts({
+        cardConfig: cardConfigPda,
+        card,
+        cardTokenAccount: tokenAccount,
+        owner: bettor3.publicKey,
+      })
+      .signers([bettor3])
+      .rpc({ commitment: "confirmed" });
+
+    const stats = (await txEvents(levelUpTx)).find((event) => event.name === "cardStatsUpdated");
+    assert.equal(stats.data.level, 1);
+    assert.equal(stats.data.experience.toNumber(), 0);
+
+    cardAccount = await program.account.card.fetch(card);
+    assert.equal(cardAccount.level, 1);
+    assert.equal(cardAccount.power, 3);
+    assert.equal(cardAccount.multiplier.toNumber(), 1025);
+    assert.equal(cardAccount.experience.toNumber(), 0);
+
+    try {
+      await program.methods
+        .levelUp()
+        .accounts({
+          cardConfig: cardConfigPda,
+          card,
+          cardTokenAccount: tokenAccount,
+          owner: bettor3.publicKey,
+        })
+        .signers([bettor3])
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("NotEnoughExperience");
+    }
+
+    console.log("✅ Battle win earned XP and the card levelled up");
+  });
+
+  it("Refunds stakes on a market expired past its resolution deadline", async () => {
+    const setGracePeriod = (seconds: number) => program.methods
+      .setResolutionGracePeriod(new BN(seconds))
+      .accounts({ platform: platformPda, authority: authority.publicKey })
+      .rpc();
+
+    await setGracePeriod(1);
+    const { market, vault } = await createMarket("Expiry refund test", 2);
+    await setGracePeriod(86_400);
+
+    const [betPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), market.toBuffer(), bettor1.publicKey.toBuffer()],
+      program.programId
+    );
+    const stake = new BN(0.2 * LAMPORTS_PER_SOL);
+    const position = await positionAccounts(market, true, bettor1.publicKey);
+
+    await program.methods
+      .placeBet(stake, true)
+      .accounts({
+        market,
+        platform: platformPda,
+        bet: betPda,
+        referrerProfile: null,
+        vault,
+        ...position,
+        bettor: bettor1.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor1])
+      .rpc();
+
+    // Nobody resolves it before end_time + 1s
+    await new Promise(resolve => setTimeout(resolve, 5000));
+    await program.methods
+      .expireMarket()
+      .accounts({ market, creatorProfile: creatorProfilePda, vault })
+      .rpc();
+
+    const marketAccount = await program.account.market.fetch(market);
+    assert.isTrue(marketAccount.resolved);
+    assert.isNull(marketAccount.outcome);
+    // The creator stake went to the pool, and the creator made no call to dispute
+    assert.isTrue(marketAccount.stakeReleased);
+    try {
+      await program.methods
+        .releaseCreatorStake()
+        .accounts({ market, creatorProfile: creatorProfilePda, creator: authority.publicKey })
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("StakeAlreadyReleased");
+    }
+    try {
+      await program.methods
+        .flagMarketDispute()
+        .accounts({ platform: platformPda, market, creatorProfile: creatorProfilePda, authority: authority.publicKey })
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("NotCreatorResolved");
+    }
+
+    const balanceBefore = await provider.connection.getBalance(bettor1.publicKey);
+    await program.methods
+      .claimRefund()
+      .accounts({
+        market,
+        vault,
+        positionMint: position.positionMint,
+        positionTokenAccount: position.positionTokenAccount,
+        bettor: bettor1.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor1])
+      .rpc();
+    const balanceAfter = await provider.connection.getBalance(bettor1.publicKey);
+
+    // A lone bettor gets the whole stake back, with no platform fee taken
+    assert.equal(balanceAfter - balanceBefore, stake.toNumber());
+    const tokens = await getAccount(provider.connection, position.positionTokenAccount);
+    assert.equal(Number(tokens.amount), 0);
+
+    console.log("✅ Expired market refunded the full stake");
+  });
+
+  it("Refunds the cost basis of voided LMSR and book shares", async () => {
+    const setGracePeriod = (seconds: number) => program.methods
+      .setResolutionGracePeriod(new BN(seconds))
+      .accounts({ platform: platformPda, authority: authority.publicKey })
+      .rpc();
+
+    await setGracePeriod(1);
+    const lmsrMarket = await createMarket("Voided LMSR test", 3);
+    const bookMarket = await createMarket("Voided book test", 3);
+    await setGracePeriod(86_400);
+
+    // LMSR: bettor1 buys YES above 0.5, so a 50/50 split would pay back less than cost
+    const [lmsrState] = PublicKey.findProgramAddressSync(
+      [Buffer.from("lmsr"), lmsrMarket.market.toBuffer()],
+      program.programId
+    );
+    const [lmsrPosition] = PublicKey.findProgramAddressSync(
+      [Buffer.from("lmsr_position"), lmsrMarket.market.toBuffer(), bettor1.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initializeLmsr(new BN(LAMPORTS_PER_SOL))
+      .accounts({
+        market: lmsrMarket.market,
+        lmsrState,
+        vault: lmsrMarket.vault,
+        creator: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    await program.methods
+      .buyLmsrShares(true, new BN(100_000_000), new BN(LAMPORTS_PER_SOL))
+      .accounts({
+        market: lmsrMarket.market,
+        platform: platformPda,
+        lmsrState,
+        position: lmsrPosition,
+        vault: lmsrMarket.vault,
+        trader: bettor1.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor1])
+      .rpc();
+
+    // Book: 100 shares cross at 0.6, so the bidder pays 600_000 and the asker 400_000
+    const { market, vault } = bookMarket;
+    const [orderBook] = PublicKey.findProgramAddressSync(
+      [Buffer.from("order_book"), market.toBuffer()],
+      program.programId
+    );
+    const [eventQueue] = PublicKey.findProgramAddressSync(
+      [Buffer.from("event_queue"), market.toBuffer()],
+      program.programId
+    );
+    const bookPosition = (owner: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from("book_position"), market.toBuffer(), owner.toBuffer()],
+      program.programId
+    )[0];
+    const bidPosition = bookPosition(bettor1.publicKey);
+    const askPosition = bookPosition(bettor2.publicKey);
+
+    await program.methods
+      .initializeOrderBook()
+      .accounts({
+        market,
+        orderBook,
+        eventQueue,
+        payer: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    for (const [bettor, side, position] of [
+      [bettor1, { bid: {} }, bidPosition],
+      [bettor2, { ask: {} }, askPosition],
+    ] as const) {
+      await program.methods
+        .placeOrder(side, new BN(6_000), new BN(100))
+        .accounts({
+          market,
+          orderBook,
+          position,
+          vault,
+          owner: bettor.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bettor])
+        .rpc();
+    }
+    await program.methods
+      .matchOrders(4)
+      .accounts({ market, platform: platformPda, orderBook, eventQueue })
+      .rpc();
+    await program.methods
+      .consumeEvents(4)
+      .accounts({ market, eventQueue })
+      .remainingAccounts([
+        { pubkey: bidPosition, isWritable: true, isSigner: false },
+        { pubkey: askPosition, isWritable: true, isSigner: false },
+      ])
+      .rpc();
+
+    // Nobody resolves either market before end_time + 1s
+    await new Promise(resolve => setTimeout(resolve, 6000));
+    for (const { market, vault } of [lmsrMarket, bookMarket]) {
+      await program.methods
+        .expireMarket()
+        .accounts({ market, creatorProfile: creatorProfilePda, vault })
+        .rpc();
+    }
+
+    const cost = (await program.account.lmsrState.fetch(lmsrState)).collected.toNumber();
+    assert.isAbove(cost, 50_000_000);
+    assert.equal((await program.account.lmsrPosition.fetch(lmsrPosition)).costBasis.toNumber(), cost);
+
+    let balanceBefore = await provider.connection.getBalance(bettor1.publicKey);
+    await program.methods
+      .claimLmsrWinnings()
+      .accounts({
+        market: lmsrMarket.market,
+        lmsrState,
+        position: lmsrPosition,
+        vault: lmsrMarket.vault,
+        trader: bettor1.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor1])
+      .rpc();
+    let balanceAfter = await provider.connection.getBalance(bettor1.publicKey);
+    assert.equal(balanceAfter - balanceBefore, cost);
+
+    // Each side gets back what it paid at the fill price
+    for (const [bettor, position, paid] of [
+      [bettor1, bidPosition, 600_000],
+      [bettor2, askPosition, 400_000],
+    ] as const) {
+      balanceBefore = await provider.connection.getBalance(bettor.publicKey);
+      await program.methods
+        .claimBookWinnings()
+        .accounts({
+          market,
+          eventQueue,
+          position,
+          vault,
+          owner: bettor.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bettor])
+        .rpc();
+      balanceAfter = await provider.connection.getBalance(bettor.publicKey);
+      assert.equal(balanceAfter - balanceBefore, paid);
+    }
+
+    console.log("✅ Voided LMSR and book shares refunded at cost");
+  });
+
+  it("Pays a card bonus from the bonus pool", async () => {
+    const { market, vault } = await createMarket("Card bonus test", 4);
+    const { card, tokenAccount } = await mintCard(bettor1, 2, 0, 1100);
+    const [bonusPool] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bonus_pool")],
+      program.programId
+    );
+    const [betPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), market.toBuffer(), bettor1.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .fundBonusPool(new BN(1 * LAMPORTS_PER_SOL))
+      .accounts({
+        platform: platformPda,
+        bonusPool,
+        funder: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .battle(new BN(1 * LAMPORTS_PER_SOL), true)
+      .accounts({
+        market,
+        platform: platformPda,
+        card,
+        cardConfig: cardConfigPda,
+        cardTokenAccount: tokenAccount,
+        cardListing: null,
+        bet: betPda,
+        referrerProfile: null,
+        vault,
+        ...(await positionAccounts(market, true, bettor1.publicKey)),
+        player: bettor1.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor1])
+      .rpc();
+
+    await program.methods
+      .placeBet(new BN(1 * LAMPORTS_PER_SOL), false)
+      .accounts({
+        market,
+        platform: platformPda,
+        bet: PublicKey.findProgramAddressSync(
+          [Buffer.from("bet"), market.toBuffer(), bettor2.publicKey.toBuffer()],
+          program.programId
+        )[0],
+        referrerProfile: null,
+        vault,
+        ...(await positionAccounts(market, false, bettor2.publicKey)),
+        bettor: bettor2.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor2])
+      .rpc();
+
+    await new Promise(resolve => setTimeout(resolve, 5000));
+    await program.methods
+      .resolveMarket(true)
+      .accounts({
+        market,
+        creatorProfile: creatorProfilePda,
+        vault,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    const poolBefore = await provider.connection.getBalance(bonusPool);
+    const paidBefore = (await program.account.platform.fetch(platformPda)).totalBonusPaid.toNumber();
+    const { positionMint, positionTokenAccount } = await positionAccounts(market, true, bettor1.publicKey);
+    const claimTx = await program.methods
+      .claimWinnings()
+      .accounts({
+        market,
+        platform: platformPda,
+        vault,
+        bet: betPda,
+        card,
+        referrerProfile: null,
+        bonusPool,
+        positionMint,
+        positionTokenAccount,
+        bettor: bettor1.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor1])
+      .rpc({ commitment: "confirmed" });
+
+    // 1.96 SOL after the 2% fee, boosted 10% by the card's 1100 multiplier
+    const claimed = (await txEvents(claimTx)).find((event) => event.name === "winningsClaimed");
+    assert.equal(claimed.data.winnings.toNumber(), 1_960_000_000);
+    assert.equal(claimed.data.bonus.toNumber(), 196_000_000);
+
+    assert.equal(poolBefore - (await provider.connection.getBalance(bonusPool)), 196_000_000);
+    const platform = await program.account.platform.fetch(platformPda);
+    assert.equal(platform.totalBonusPaid.toNumber() - paidBefore, 196_000_000);
+    assert.equal((await program.account.bet.fetch(betPda)).bonusPaid.toNumber(), 196_000_000);
+
+    console.log("✅ Card bonus paid from the bonus pool");
+  });
+
+  it("Caps a card's concurrent battles by rarity and enforces the cooldown", async () => {
+    const first = await createMarket("Battle cap test 1", 4);
+    const second = await createMarket("Battle cap test 2", 60);
+    // A Legendary card may only be in one battle at a time
+    const { card, tokenAccount } = await mintCard(bettor2, 9, 4, 2500);
+    const betPda = (market: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), market.toBuffer(), bettor2.publicKey.toBuffer()],
+      program.programId
+    )[0];
+    const battle = async ({ market, vault }: { market: PublicKey; vault: PublicKey }) =>
+      program.methods
+        .battle(new BN(0.1 * LAMPORTS_PER_SOL), true)
+        .accounts({
+          market,
+          platform: platformPda,
+          card,
+          cardConfig: cardConfigPda,
+          cardTokenAccount: tokenAccount,
+          cardListing: null,
+          bet: betPda(market),
+          referrerProfile: null,
+          vault,
+          ...(await positionAccounts(market, true, bettor2.publicKey)),
+          player: bettor2.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bettor2])
+        .rpc();
+
+    await battle(first);
+    try {
+      await battle(second);
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("TooManyActiveBattles");
+    }
+
+    await new Promise(resolve => setTimeout(resolve, 5000));
+    await program.methods
+      .resolveMarket(true)
+      .accounts({
+        market: first.market,
+        creatorProfile: creatorProfilePda,
+        vault: first.vault,
+        authority: authority.publicKey,
+      })
+      .rpc();
+    await program.methods
+      .settleBet()
+      .accounts({
+        market: first.market,
+        bet: betPda(first.market),
+        card,
+        userProfile: PublicKey.findProgramAddressSync(
+          [Buffer.from("user_profile"), bettor2.publicKey.toBuffer()],
+          program.programId
+        )[0],
+      })
+      .rpc();
+    assert.equal((await program.account.card.fetch(card)).activeBattles, 0);
+
+    // The slot is free again, but the card rests for the configured hour
+    try {
+      await battle(second);
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("CardOnCooldown");
+    }
+
+    console.log("✅ Rarity cap and post-battle cooldown enforced");
+  });
+
+  it("Settles an accepted duel and pays the winner both stakes", async () => {
+    const { market, vault } = await createMarket("Duel settlement test", 4);
+    const challenger = await mintCard(bettor1, 2, 0, 1000);
+    const opponent = await mintCard(bettor2, 3, 0, 1000);
+    const [duel] = PublicKey.findProgramAddressSync(
+      [Buffer.from("duel"), market.toBuffer(), challenger.card.toBuffer()],
+      program.programId
+    );
+    const stake = 0.5 * LAMPORTS_PER_SOL;
+
+    await program.methods
+      .createDuel(new BN(stake), true)
+      .accounts({
+        market,
+        cardConfig: cardConfigPda,
+        card: challenger.card,
+        cardTokenAccount: challenger.tokenAccount,
+        duel,
+        challenger: bettor1.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor1])
+      .rpc();
+
+    await program.methods
+      .acceptDuel()
+      .accounts({
+        market,
+        cardConfig: cardConfigPda,
+        duel,
+        card: opponent.card,
+        cardTokenAccount: opponent.tokenAccount,
+        opponent: bettor2.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor2])
+      .rpc();
+
+    const duelAccount = await program.account.duel.fetch(duel);
+    assert.equal(duelAccount.opponent.toBase58(), bettor2.publicKey.toBase58());
+    assert.equal(duelAccount.opponentCard.toBase58(), opponent.card.toBase58());
+
+    await new Promise(resolve => setTimeout(resolve, 5000));
+    await program.methods
+      .resolveMarket(true)
+      .accounts({
+        market,
+        creatorProfile: creatorProfilePda,
+        vault,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    // The challenger called YES, so the pot and the duel's rent go to bettor1
+    const escrowed = await provider.connection.getBalance(duel);
+    const challengerBefore = await provider.connection.getBalance(bettor1.publicKey);
+    const opponentBefore = await provider.connection.getBalance(bettor2.publicKey);
+    const settleTx = await program.methods
+      .settleDuel()
+      .accounts({
+        market,
+        duel,
+        challengerCard: challenger.card,
+        opponentCard: opponent.card,
+        challenger: bettor1.publicKey,
+        opponent: bettor2.publicKey,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const settled = (await txEvents(settleTx)).find((event) => event.name === "duelSettled");
+    assert.equal(settled.data.winner.toBase58(), bettor1.publicKey.toBase58());
+    assert.equal(settled.data.payout.toNumber(), 2 * stake);
+
+    assert.equal(await provider.connection.getBalance(bettor1.publicKey) - challengerBefore, escrowed);
+    assert.equal(await provider.connection.getBalance(bettor2.publicKey), opponentBefore);
+    assert.isNull(await provider.connection.getAccountInfo(duel));
+
+    // Even odds: XP is twice the stake in 0.01 SOL units
+    const winnerCard = await program.account.card.fetch(challenger.card);
+    assert.equal(winnerCard.wins.toNumber(), 1);
+    assert.equal(winnerCard.experience.toNumber(), 100);
+    assert.equal(winnerCard.activeBattles, 0);
+    const loserCard = await program.account.card.fetch(opponent.card);
+    assert.equal(loserCard.losses.toNumber(), 1);
+    assert.equal(loserCard.activeBattles, 0);
+
+    console.log("✅ Duel settled and the winner took both stakes");
+  });
+
+  it("Rents a card, battles with it and reclaims it after expiry", async () => {
+    const listingAccounts = async (mint: PublicKey) => {
+      const [cardListing] = PublicKey.findProgramAddressSync(
+        [Buffer.from("card_listing"), mint.toBuffer()],
+        program.programId
+      );
+      return { cardListing, escrowTokenAccount: await getAssociatedTokenAddress(mint, cardListing, true) };
+    };
+    const listCard = async (mint: PublicKey, card: PublicKey, lenderTokenAccount: PublicKey, duration: number) =>
+      program.methods
+        .listCardForRent(new BN(0.01 * LAMPORTS_PER_SOL), new BN(duration))
+        .accounts({
+          card,
+          mint,
+          ...(await listingAccounts(mint)),
+          lenderTokenAccount,
+          lender: bettor1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bettor1])
+        .rpc();
+    const { market, vault } = await createMarket("Card rental test", 60);
+    const betPda = (bettor: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), market.toBuffer(), bettor.toBuffer()],
+      program.programId
+    )[0];
+
+    // A card in an open battle cannot be listed
+    const busy = await mintCard(bettor1, 2, 0, 1000);
+    await program.methods
+      .battle(new BN(0.1 * LAMPORTS_PER_SOL), true)
+      .accounts({
+        market,
+        platform: platformPda,
+        card: busy.card,
+        cardConfig: cardConfigPda,
+        cardTokenAccount: busy.tokenAccount,
+        cardListing: null,
+        bet: betPda(bettor1.publicKey),
+        referrerProfile: null,
+        vault,
+        ...(await positionAccounts(market, true, bettor1.publicKey)),
+        player: bettor1.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor1])
+      .rpc();
+    try {
+      await listCard(busy.mint, busy.card, busy.tokenAccount, 6);
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("CardInBattle");
+    }
+
+    const { mint, card, tokenAccount } = await mintCard(bettor1, 2, 0, 1000);
+    const { cardListing, escrowTokenAccount } = await listingAccounts(mint);
+    await listCard(mint, card, tokenAccount, 6);
+
+    const lenderBefore = await provider.connection.getBalance(bettor1.publicKey);
+    await program.methods
+      .rentCard()
+      .accounts({
+        cardListing,
+        lender: bettor1.publicKey,
+        renter: bettor2.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor2])
+      .rpc();
+    assert.equal(await provider.connection.getBalance(bettor1.publicKey) - lenderBefore, 0.01 * LAMPORTS_PER_SOL);
+    assert.equal((await program.account.cardListing.fetch(cardListing)).renter.toBase58(), bettor2.publicKey.toBase58());
+
+    // The renter plays the escrowed card; the lender stays its owner
+    await program.methods
+      .battle(new BN(0.1 * LAMPORTS_PER_SOL), false)
+      .accounts({
+        market,
+        platform: platformPda,
+        card,
+        cardConfig: cardConfigPda,
+        cardTokenAccount: escrowTokenAccount,
+        cardListing,
+        bet: betPda(bettor2.publicKey),
+        referrerProfile: null,
+        vault,
+        ...(await positionAccounts(market, false, bettor2.publicKey)),
+        player: bettor2.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettor2])
+      .rpc();
+    assert.equal((await program.account.bet.fetch(betPda(bettor2.publicKey))).cardMint.toBase58(), mint.toBase58());
+    const cardAccount = await program.account.card.fetch(card);
+    assert.equal(cardAccount.activeBattles, 1);
+    assert.equal(cardAccount.owner.toBase58(), bettor1.publicKey.toBase58());
+
+    const reclaim = () =>
+      program.methods
+        .reclaimCard()
+        .accounts({
+          cardListing,
+          escrowTokenAccount,
+          lenderTokenAccount: tokenAccount,
+          lender: bettor1.publicKey,
+          caller: bettor3.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bettor3])
+        .rpc();
+
+    try {
+      await reclaim();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("CardRented");
+    }
+
+    // Once the rental lapses anyone can return the card to its lender
+    await new Promise(resolve => setTimeout(resolve, 7000));
+    await reclaim();
+
+    const lenderAccount = await getAccount(provider.connection, tokenAccount);
+    assert.equal(Number(lenderAccount.amount), 1);
+    assert.isNull(await provider.connection.getAccountInfo(cardListing));
+
+    console.log("✅ Rented card battled for its renter and returned after expiry");
+  });
+
+  it("Pays the referrer of a battle bet even after the fee is collected", async () => {
+    await program.methods
+      .setReferralFeeShare(5000)
+      .accounts({ platform: platformPda, authority: authority.publicKey })
+      .rpc();
+
+    const referrer = Keypair.generate();
+    const referred = Keypair.generate();
+    for (const wallet of [referrer, referred]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(wallet.publicKey, 2 * LAMPORTS_PER_SOL)
+      );
+    }
+    const profilePda = (owner: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from("user_profile"), owner.toBuffer()],
+      program.programId
+    )[0];
+    const referrerProfile = profilePda(referrer.publicKey);
+
+    const { market, vault } = await createMarket("Referral payout test", 4);
+    const betPda = (bettor: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), market.toBuffer(), bettor.toBuffer()],
+      program.programId
+    )[0];
+
+    // The referrer's own bet creates the profile rewards are credited to
+    await program.methods
+      .placeBet(new BN(1 * LAMPORTS_PER_SOL), false)
+      .accounts({
+        market,
+        platform: platformPda,
+        bet: betPda(referrer.publicKey),
+        referrerProfile: null,
+        vault,
+        ...(await positionAccounts(market, false, referrer.publicKey)),
+        bettor: referrer.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([referrer])
+      .rpc();
+
+    // The referred wallet's first bet is a battle
+    const { card, tokenAccount } = await mintCard(referred, 2, 0, 1000);
+    await program.methods
+      .battle(new BN(1 * LAMPORTS_PER_SOL), true)
+      .accounts({
+        market,
+        platform: platformPda,
+        card,
+        cardConfig: cardConfigPda,
+        cardTokenAccount: tokenAccount,
+        cardListing: null,
+        bet: betPda(referred.publicKey),
+        referrerProfile,
+        vault,
+        ...(await positionAccounts(market, true, referred.publicKey)),
+        player: referred.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([referred])
+      .rpc();
+    const referredAccount = await program.account.userProfile.fetch(profilePda(referred.publicKey));
+    assert.equal(referredAccount.referrer.toBase58(), referrer.publicKey.toBase58());
+
+    await new Promise(resolve => setTimeout(resolve, 5000));
+    await program.methods
+      .resolveMarket(true)
+      .accounts({
+        market,
+        creatorProfile: creatorProfilePda,
+        vault,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    // Collecting first keeps the referrers' half of the 0.04 SOL fee in the vault
+    const treasuryBefore = await provider.connection.getBalance(treasuryKeypair.publicKey);
+    await program.methods
+      .collectPlatformFee()
+      .accounts({
+        market,
+        platform: platformPda,
+        vault,
+        treasury: treasuryKeypair.publicKey,
+        leaderboard: null,
+        authority: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    assert.equal(await provider.connection.getBalance(treasuryKeypair.publicKey) - treasuryBefore, 20_000_000);
+
+    const { positionMint, positionTokenAccount } = await positionAccounts(market, true, referred.publicKey);
+    await program.methods
+      .claimWinnings()
+      .accounts({
+        market,
+        platform: platformPda,
+        vault,
+        bet: betPda(referred.publicKey),
+        card,
+        referrerProfile,
+        positionMint,
+        positionTokenAccount,
+        bettor: referred.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([referred])
+      .rpc();
+
+    const referrerAccount = await program.account.userProfile.fetch(referrerProfile);
+    assert.equal(referrerAccount.referralRewards.toNumber(), 20_000_000);
+    assert.equal((await program.account.market.fetch(market)).referralPaid.toNumber(), 20_000_000);
+
+    const referrerBefore = await provider.connection.getBalance(referrer.publicKey);
+    await program.methods
+      .claimReferralRewards()
+      .accounts({ userProfile: referrerProfile, owner: referrer.publicKey })
+      .signers([referrer])
+      .rpc();
+    assert.equal(await provider.connection.getBalance(referrer.publicKey) - referrerBefore, 20_000_000);
+
+    await program.methods
+      .setReferralFeeShare(0)
+      .accounts({ platform: platformPda, authority: authority.publicKey })
+      .rpc();
+
+    console.log("✅ Referrer paid their share of the referred winner's fee");
+  });
+
+  it("Slashes the bond to the treasury when a resolution is overturned", async () => {
+    const setBond = (bond: number) => program.methods
+      .setManualMarketBond(new BN(bond))
+      .accounts({ platform: platformPda, authority: authority.publicKey })
+      .rpc();
+
+    const bond = 0.1 * LAMPORTS_PER_SOL;
+    await setBond(bond);
+    const { market, vault } = await createMarket("Dispute overturn test", 2);
+    await setBond(0);
+    assert.equal((await program.account.market.fetch(market)).creatorBond.toNumber(), bond);
+
+    await new Promise(resolve => setTimeout(resolve, 3000));
+    await program.methods
+      .resolveMarket(true)
+      .accounts({
+        market,
+        creatorProfile: creatorProfilePda,
+        vault,
+        authority: authority.publicKey,
+      })
+      .rpc();
+    await program.methods
+      .flagMarketDispute()
+      .accounts({ platform: platformPda, market, creatorProfile: creatorProfilePda, authority: authority.publicKey })
+      .rpc();
+
+    const lockedBefore = (await program.account.creatorProfile.fetch(creatorProfilePda)).lockedStake.toNumber();
+    const treasuryBefore = await provider.connection.getBalance(treasuryKeypair.publicKey);
+    await program.methods
+      .ruleMarketDispute(true)
+      .accounts({
+        platform: platformPda,
+        market,
+        creatorProfile: creatorProfilePda,
+        treasury: treasuryKeypair.publicKey,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    // The outcome stands for claims already paid, so the bond leaves the pool
+    assert.equal(await provider.connection.getBalance(treasuryKeypair.publicKey) - treasuryBefore, bond);
+    const marketAccount = await program.account.market.fetch(market);
+    assert.property(marketAccount.dispute, "overturned");
+    assert.equal(marketAccount.creatorBond.toNumber(), 0);
+    assert.equal(marketAccount.forfeitedAmount.toNumber(), 0);
+    const creatorProfile = await program.account.creatorProfile.fetch(creatorProfilePda);
+    assert.equal(lockedBefore - creatorProfile.lockedStake.toNumber(), bond);
+
+    console.log("✅ Overturned resolution slashed the bond to the treasury");
+  });
+
+  // Ends season 1, so it has to stay the last test
+  it("Funds the leaderboard from fees and pays the ranked wallets", async () => {
+    await program.methods
+      .setLeaderboardFeeShare(5000)
+      .accounts({ platform: platformPda, authority: authority.publicKey })
+      .rpc();
+    await program.methods
+      .setLeaderboardSettlementPeriod(new BN(1))
+      .accounts({ platform: platformPda, authority: authority.publicKey })
+      .rpc();
+
+    const winner = Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(winner.publicKey, 2 * LAMPORTS_PER_SOL)
+    );
+    const [winnerProfile] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_profile"), winner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const { market, vault } = await createMarket("Leaderboard prize test", 4);
+    const betPda = (bettor: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), market.toBuffer(), bettor.toBuffer()],
+      program.programId
+    )[0];
+    for (const [bettor, prediction] of [[winner, true], [bettor2, false]] as const) {
+      await program.methods
+        .placeBet(new BN(1 * LAMPORTS_PER_SOL), prediction)
+        .accounts({
+          market,
+          platform: platformPda,
+          bet: betPda(bettor.publicKey),
+          referrerProfile: null,
+          vault,
+          ...(await positionAccounts(market, prediction, bettor.publicKey)),
+          bettor: bettor.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bettor])
+        .rpc();
+    }
+
+    await new Promise(resolve => setTimeout(resolve, 5000));
+    await program.methods
+      .resolveMarket(true)
+      .accounts({
+        market,
+        creatorProfile: creatorProfilePda,
+        vault,
+        authority: authority.publicKey,
+      })
+      .rpc();
+    await program.methods
+      .settleBet()
+      .accounts({ market, bet: betPda(winner.publicKey), card: null, userProfile: winnerProfile })
+      .rpc();
+
+    // Half of the 0.04 SOL fee on the 2 SOL pool funds the season's prizes
+    const treasuryBefore = await provider.connection.getBalance(treasuryKeypair.publicKey);
+    await program.methods
+      .collectPlatformFee()
+      .accounts({
+        market,
+        platform: platformPda,
+        vault,
+        treasury: treasuryKeypair.publicKey,
+        leaderboard: leaderboardPda,
+        authority: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    assert.equal(await provider.connection.getBalance(treasuryKeypair.publicKey) - treasuryBefore, 20_000_000);
+    assert.equal((await program.account.leaderboard.fetch(leaderboardPda)).prizePool.toNumber(), 20_000_000);
+
+    await program.methods
+      .submitLeaderboardEntry()
+      .accounts({ season: seasonPda, leaderboard: leaderboardPda, userProfile: winnerProfile })
+      .rpc();
+    await program.methods
+      .endSeason()
+      .accounts({
+        platform: platformPda,
+        cardConfig: cardConfigPda,
+        season: seasonPda,
+        caller: authority.publicKey,
+      })
+      .rpc();
+
+    await new Promise(resolve => setTimeout(resolve, 2000));
+    const winnerBefore = await provider.connection.getBalance(winner.publicKey);
+    const treasuryBeforePrizes = await provider.connection.getBalance(treasuryKeypair.publicKey);
+    await program.methods
+      .distributeLeaderboardPrizes()
+      .accounts({
+        platform: platformPda,
+        season: seasonPda,
+        leaderboard: leaderboardPda,
+        treasury: treasuryKeypair.publicKey,
+      })
+      .remainingAccounts([
+        { pubkey: winnerProfile, isWritable: false, isSigner: false },
+        { pubkey: winner.publicKey, isWritable: true, isSigner: false },
+      ])
+      .rpc();
+
+    // First place takes 30%; the empty ranks' shares return to the treasury
+    assert.equal(await provider.connection.getBalance(winner.publicKey) - winnerBefore, 6_000_000);
+    assert.equal(await provider.connection.getBalance(treasuryKeypair.publicKey) - treasuryBeforePrizes, 14_000_000);
+    const leaderboard = await program.account.leaderboard.fetch(leaderboardPda);
+    assert.isTrue(leaderboard.distributed);
+    assert.equal(leaderboard.prizePool.toNumber(), 0);
+
+    console.log("✅ Leaderboard prizes paid from the platform fee share");
   });
 });